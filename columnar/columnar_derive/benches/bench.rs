@@ -50,6 +50,7 @@ const fn gen_mix<const N: usize>(r: usize) -> [usize; N] {
 }
 
 #[derive(Columnar)]
+#[columnar(zeroable)]
 pub struct FourFloats {
     f01: f32,
     f02: f32,
@@ -120,7 +121,7 @@ unsafe fn read_array<T: Copy, const N: usize>(ptr: *const T) -> [T; N] {
 }
 
 fn bench_struct_of_arrays(c: &mut Criterion) {
-    let arrays = StructOfArrays::<FourFloatsPtrs>::new(128_000);
+    let arrays = StructOfArrays::<FourFloatsPtrs>::zeroed(128_000);
     c.bench_function("StructOfArrays::<FourFloatPtrs>::row", |b| {
         b.iter(|| black_box(arrays.row(0)))
     });
@@ -135,6 +136,18 @@ fn bench_struct_of_arrays(c: &mut Criterion) {
                 .collect::<ArrayVec<FourFloats, 10240>>()
         })
     });
+    c.bench_function(
+        "StructOfArrays::<FourFloatPtrs>::iter_refs_two_fields",
+        |b| {
+            b.iter(|| {
+                arrays
+                    .iter_refs()
+                    .take(10240)
+                    .map(|row| *row.f01() + *row.f59())
+                    .sum::<f32>()
+            })
+        },
+    );
     c.bench_function(
         "StructOfArrays::<FourFloatPtrs>::iter_chunked<10>_all",
         |b| {
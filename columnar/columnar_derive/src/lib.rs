@@ -2,8 +2,10 @@ use proc_macro2::TokenStream;
 use quote::format_ident;
 use quote::quote;
 use quote::ToTokens;
+use syn::punctuated::Punctuated;
 use syn::Attribute;
-use syn::{parse_macro_input, Data, DeriveInput, Field, Fields, FieldsNamed, Type};
+use syn::{bracketed, parenthesized, Ident, Token};
+use syn::{parse_macro_input, Data, DeriveInput, Field, Fields, FieldsNamed, LitInt, Type};
 
 // Is 0?
 // pub struct Foo {
@@ -16,18 +18,180 @@ use syn::{parse_macro_input, Data, DeriveInput, Field, Fields, FieldsNamed, Type
 ///    b. nullability
 /// 5. columnar-arrow / examples
 
-#[proc_macro_derive(Columnar, attributes(nested))]
+/// Options set through `#[columnar(...)]` on the derived struct.
+#[derive(Default)]
+struct Options {
+    /// Allocate every column out of one block instead of one block per field.
+    contiguous: bool,
+    /// Minimum alignment of every column, in bytes.
+    align: Option<usize>,
+    /// Implement `Zeroable`, which every field's columns must also do.
+    zeroable: bool,
+    /// Row types projecting a subset of the fields, from
+    /// `view(Name = [a, b], derive(Debug))`.
+    views: Vec<View>,
+}
+
+struct View {
+    name: Ident,
+    fields: Vec<Ident>,
+    /// Traits to derive on the view, from `derive(...)` next to its name.
+    derives: Vec<syn::Path>,
+}
+
+fn parse_options(attrs: &[Attribute]) -> syn::Result<Options> {
+    let mut options = Options::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("columnar")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("contiguous") {
+                options.contiguous = true;
+                Ok(())
+            } else if meta.path.is_ident("align") {
+                let align: usize = meta.value()?.parse::<LitInt>()?.base10_parse()?;
+                if !align.is_power_of_two() {
+                    return Err(meta.error("align must be a power of two"));
+                }
+                options.align = Some(align);
+                Ok(())
+            } else if meta.path.is_ident("zeroable") {
+                options.zeroable = true;
+                Ok(())
+            } else if meta.path.is_ident("view") {
+                let first = options.views.len();
+                let mut derives = Vec::new();
+                meta.parse_nested_meta(|view| {
+                    if view.path.is_ident("derive") {
+                        let paths;
+                        parenthesized!(paths in view.input);
+                        derives.extend(Punctuated::<syn::Path, Token![,]>::parse_terminated(
+                            &paths,
+                        )?);
+                        return Ok(());
+                    }
+                    let name = view.path.require_ident()?.clone();
+                    let value = view.value()?;
+                    let fields;
+                    bracketed!(fields in value);
+                    let fields = Punctuated::<Ident, Token![,]>::parse_terminated(&fields)?;
+                    options.views.push(View {
+                        name,
+                        fields: fields.into_iter().collect(),
+                        derives: Vec::new(),
+                    });
+                    Ok(())
+                })?;
+                for view in &mut options.views[first..] {
+                    view.derives.clone_from(&derives);
+                }
+                Ok(())
+            } else {
+                Err(meta.error("unsupported columnar option"))
+            }
+        })?;
+    }
+    Ok(options)
+}
+
+#[proc_macro_derive(Columnar, attributes(nested, columnar))]
 pub fn columnar(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     //input
     // Parse the input tokens into a syntax tree
     let input = parse_macro_input!(input as DeriveInput);
 
+    let options = match parse_options(&input.attrs) {
+        Ok(options) => options,
+        Err(err) => return err.to_compile_error().into(),
+    };
     let name = input.ident;
     let named_fields = named_fields(&input.data);
     let columnar_name = format_ident!("{name}Ptrs");
+    let slices_name = format_ident!("{name}Slices");
+    let slices_mut_name = format_ident!("{name}SlicesMut");
+    let slices_mut_doc =
+        format!("Mutably borrowed columns of [`{name}`] rows, one slice per field.");
+    let ref_name = format_ident!("{name}Ref");
+    let ref_doc = format!("A [`{name}`] row whose fields are only read when accessed.");
+    let ref_mut_name = format_ident!("{name}RefMut");
+    let ref_mut_doc = format!("Mutable references to every field of one [`{name}`] row.");
+    let slices_doc = format!("Borrowed columns of [`{name}`] rows, one slice per field.");
+
+    let views = match views(&options.views, &name, &columnar_name, named_fields) {
+        Ok(views) => views,
+        Err(err) => return err.to_compile_error().into(),
+    };
 
     let fields_comma = fields_comma(named_fields);
-    let fields_alloc = fields_alloc(named_fields);
+    let fields_layout = fields_layout(named_fields);
+    let fields_from_raw_parts = fields_from_raw_parts(named_fields);
+    let base_ptr = base_ptr(named_fields);
+    let fields_describe = fields_describe(named_fields);
+    let fields_lanes = fields_lanes(named_fields);
+    let align = options
+        .align
+        .map(|align| quote!(const ALIGN: usize = #align;));
+    let (alloc, dealloc, realloc) = if options.contiguous {
+        (
+            quote!(columnar_trait::__private::alloc_contiguous(
+                len, align, alloc
+            )),
+            quote!(columnar_trait::__private::dealloc_contiguous(
+                self, len, align, alloc
+            )),
+            quote!(columnar_trait::__private::realloc_contiguous(
+                self, old_len, new_len, align, alloc
+            )),
+        )
+    } else {
+        let fields_alloc = fields_alloc(named_fields);
+        (
+            quote! {
+                unsafe {
+                    #fields_alloc
+                    Self {
+                        #fields_comma
+                    }
+                }
+            },
+            fields_dealloc(named_fields),
+            fields_realloc(named_fields),
+        )
+    };
+    let fields_offset = fields_offset(named_fields);
+    let slices_fields = slices_fields(named_fields);
+    let slices_args = slices_args(named_fields);
+    let slices_init = if named_fields.named.is_empty() {
+        quote!(_lt: std::marker::PhantomData,)
+    } else {
+        fields_comma.clone()
+    };
+    let slices_len = slices_len(named_fields);
+    let slices_len_eq = slices_len_eq(named_fields);
+    let fields_from_slices = fields_from_slices(named_fields);
+    let slices_accessors = slices_accessors(named_fields);
+    let slices_mut_fields = slices_mut_fields(named_fields);
+    let slices_mut_accessors = slices_mut_accessors(named_fields);
+    let fields_slices_mut = fields_slices(named_fields, quote!(slices_mut));
+    let fields_slices = fields_slices(named_fields, quote!(slices));
+    let fields_reborrow = fields_reborrow(named_fields);
+    let ref_fields = ref_fields(named_fields);
+    let ref_accessors = ref_accessors(named_fields);
+    let fields_row_ref = fields_row_ref(named_fields);
+    let ref_mut_fields = ref_mut_fields(named_fields);
+    let fields_row_mut = fields_row_mut(named_fields);
+    let fields_copy = fields_copy(named_fields);
+    let fields_eq = fields_eq(named_fields);
+    let fields_write = fields_write(named_fields);
+    let zeroable = options.zeroable.then(|| {
+        let fields_zero = fields_zero(named_fields);
+        quote! {
+            unsafe impl columnar_trait::Zeroable for #columnar_name {
+                #[inline]
+                unsafe fn write_zeroes(&self, count: usize) {
+                    #fields_zero
+                }
+            }
+        }
+    });
     let fields_read = fields_read(named_fields);
     let fields_chunk = fields_chunk(named_fields);
     let fields_index = fields_index(named_fields);
@@ -39,21 +203,180 @@ pub fn columnar(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
            #columnar_fields
        }
 
+       #[doc = #slices_doc]
+       #[derive(Debug, Clone, Copy)]
+       pub struct #slices_name<'a> {
+           #slices_fields
+       }
+
+       impl<'a> #slices_name<'a> {
+           /// Borrows one slice per field, or returns `None` unless they all
+           /// have the same length.
+           #[allow(clippy::too_many_arguments)]
+           pub fn new(#slices_args) -> Option<Self> {
+               let slices = Self { #slices_init };
+               let len = <#columnar_name as columnar_trait::ArrayPtr>::slices_len(slices);
+               if true #slices_len_eq {
+                   Some(slices)
+               } else {
+                   None
+               }
+           }
+
+           #slices_accessors
+       }
+
+       #[doc = #slices_mut_doc]
+       #[derive(Debug)]
+       pub struct #slices_mut_name<'a> {
+           #slices_mut_fields
+       }
+
+       impl<'a> #slices_mut_name<'a> {
+           #slices_mut_accessors
+       }
+
+       #[doc = #ref_doc]
+       #[derive(Debug, Clone, Copy)]
+       pub struct #ref_name<'a> {
+           #ref_fields
+       }
+
+       impl<'a> #ref_name<'a> {
+           #ref_accessors
+       }
+
+       #[doc = #ref_mut_doc]
+       #[derive(Debug)]
+       pub struct #ref_mut_name<'a> {
+           #ref_mut_fields
+       }
+
        impl columnar_trait::ArrayRow for #name {
         type Ptr = #columnar_name;
        }
 
        impl columnar_trait::ArrayPtr for #columnar_name {
            type Row = #name;
+           type Slices<'a> = #slices_name<'a>;
+           type SlicesMut<'a> = #slices_mut_name<'a>;
+           type Ref<'a> = #ref_name<'a>;
+           type RefMut<'a> = #ref_mut_name<'a>;
+
+           #align
 
            #[inline]
-           fn new(len: usize) -> Self {
-            unsafe {
-                #fields_alloc
-                Self {
-                    #fields_comma
-                }
-            }
+           fn lanes(align: usize) -> usize {
+               let lanes = 1;
+               #fields_lanes
+               lanes
+           }
+
+           #[inline]
+           fn new<__Alloc: columnar_trait::ColumnAllocator>(
+               len: usize,
+               align: usize,
+               alloc: &__Alloc,
+           ) -> Self {
+               #alloc
+           }
+
+           #[inline]
+           fn layout(len: usize, align: usize) -> std::alloc::Layout {
+               let layout = std::alloc::Layout::new::<()>();
+               #fields_layout
+               layout
+           }
+
+           #[inline]
+           unsafe fn from_raw_parts(base: *mut u8, len: usize, align: usize) -> Self {
+               let layout = std::alloc::Layout::new::<()>();
+               #fields_from_raw_parts
+               Self {
+                   #fields_comma
+               }
+           }
+
+           #[inline]
+           fn base_ptr(&self) -> *const u8 {
+               #base_ptr
+           }
+
+           fn describe_columns(columns: &mut Vec<std::alloc::Layout>) {
+               #fields_describe
+           }
+
+           #[inline]
+           fn slices_len(slices: Self::Slices<'_>) -> usize {
+               #slices_len
+           }
+
+           #[inline]
+           unsafe fn slices<'a>(&self, len: usize) -> Self::Slices<'a> {
+               #slices_name {
+                   #fields_slices
+               }
+           }
+
+           #[inline]
+           unsafe fn slices_mut<'a>(&self, len: usize) -> Self::SlicesMut<'a> {
+               #slices_mut_name {
+                   #fields_slices_mut
+               }
+           }
+
+           #[inline]
+           fn reborrow_slices_mut<'b>(
+               slices: &'b mut Self::SlicesMut<'_>,
+           ) -> Self::SlicesMut<'b> {
+               #slices_mut_name {
+                   #fields_reborrow
+               }
+           }
+
+           #[inline]
+           fn from_slices(slices: Self::Slices<'_>) -> Self {
+               Self {
+                   #fields_from_slices
+               }
+           }
+
+           #[inline]
+           unsafe fn offset(&self, count: usize) -> Self {
+               Self {
+                   #fields_offset
+               }
+           }
+
+           #[inline]
+           unsafe fn dealloc<__Alloc: columnar_trait::ColumnAllocator>(
+               &mut self,
+               len: usize,
+               align: usize,
+               alloc: &__Alloc,
+           ) {
+               #dealloc
+           }
+
+           #[inline]
+           unsafe fn realloc<__Alloc: columnar_trait::ColumnAllocator>(
+               &mut self,
+               old_len: usize,
+               new_len: usize,
+               align: usize,
+               alloc: &__Alloc,
+           ) {
+               #realloc
+           }
+
+           #[inline]
+           unsafe fn copy_rows(&self, dst: &Self, count: usize) {
+               #fields_copy
+           }
+
+           #[inline]
+           unsafe fn rows_eq(&self, other: &Self, len: usize) -> bool {
+               true #fields_eq
            }
 
            #[inline]
@@ -80,13 +403,101 @@ pub fn columnar(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
                 }
                 // let idx = idx as isize;
             }
+
+           #[inline]
+           unsafe fn row_ref<'a>(&self, idx: usize) -> Self::Ref<'a> {
+               #ref_name {
+                   #fields_row_ref
+               }
+           }
+
+           #[inline]
+           unsafe fn row_mut<'a>(&self, idx: usize) -> Self::RefMut<'a> {
+               #ref_mut_name {
+                   #fields_row_mut
+               }
+           }
+
+           #[inline]
+           unsafe fn write(&self, idx: usize, row: Self::Row) {
+               let Self::Row { #fields_comma } = row;
+               #fields_write
+           }
        }
+
+       #views
+
+       #zeroable
     };
 
     // Hand the output tokens back to the compiler
     proc_macro::TokenStream::from(expanded)
 }
 
+/// Generates a row type and its `Projection` impl for every view.
+fn views(
+    views: &[View],
+    name: &Ident,
+    columnar_name: &Ident,
+    fields: &FieldsNamed,
+) -> syn::Result<TokenStream> {
+    let mut expanded = TokenStream::new();
+    for view in views {
+        let projected = view
+            .fields
+            .iter()
+            .map(|ident| {
+                fields
+                    .named
+                    .iter()
+                    .find(|f| f.ident.as_ref() == Some(ident))
+                    .ok_or_else(|| {
+                        syn::Error::new(ident.span(), format!("no field `{ident}` on `{name}`"))
+                    })
+            })
+            .collect::<syn::Result<Vec<_>>>()?;
+        let view_name = &view.name;
+        let doc = format!("Projection of [`{name}`] rows read by `iter_view` and `chunk_view`.");
+        let view_fields = projected.iter().map(|f| {
+            let name = &f.ident;
+            let vis = &f.vis;
+            let ty = &f.ty;
+            quote!(#vis #name: #ty,)
+        });
+        let names = projected.iter().map(|f| &f.ident).collect::<Vec<_>>();
+        let derives = &view.derives;
+        let derives = (!derives.is_empty()).then(|| quote!(#[derive(#(#derives),*)]));
+        expanded.extend(quote! {
+            #[doc = #doc]
+            #derives
+            pub struct #view_name {
+                #(#view_fields)*
+            }
+
+            impl columnar_trait::Projection<#columnar_name> for #view_name {
+                #[inline]
+                unsafe fn read(ptrs: &#columnar_name, idx: usize) -> Self {
+                    Self {
+                        #(#names: columnar_trait::ArrayPtr::row(&ptrs.#names, idx),)*
+                    }
+                }
+
+                #[inline]
+                unsafe fn read_chunk<const N: usize>(ptrs: &#columnar_name, idx: usize) -> [Self; N] {
+                    #(
+                        let chunk: [_; N] = columnar_trait::ArrayPtr::chunk(&ptrs.#names, idx);
+                        let mut #names = chunk.into_iter();
+                    )*
+                    std::array::from_fn(|_| Self {
+                        #(#names: #names.next().unwrap(),)*
+                    })
+                }
+            }
+        });
+    }
+    Ok(expanded)
+}
+
 fn named_fields(data: &Data) -> &FieldsNamed {
     match *data {
         Data::Struct(ref data) => match data.fields {
@@ -126,13 +537,326 @@ fn columnar_fields(fields: &FieldsNamed) -> TokenStream {
 fn fields_alloc(fields: &FieldsNamed) -> TokenStream {
     let columnar_fields = fields.named.iter().map(|f| {
         let name = &f.ident;
-        let ty = &f.ty;
-        quote!(let #name = <#ty as columnar_trait::ArrayRow>::Ptr::new(len);)
+        let ptr = field_ptr(f);
+        let align = field_align(f);
+        quote!(let #name = <#ptr as columnar_trait::ArrayPtr>::new(len, #align, alloc);)
         //quote!(let #name = alloc(std::alloc::Layout::array::<#ty>(len).unwrap()).cast();)
     });
     quote!(#(#columnar_fields)*)
 }
 
+fn field_ptr(f: &Field) -> TokenStream {
+    let ty = &f.ty;
+    quote!(<#ty as columnar_trait::ArrayRow>::Ptr)
+}
+
+/// Alignment of a field's columns: the parent's, unless the field's own
+/// type asks for more.
+fn field_align(f: &Field) -> TokenStream {
+    let ptr = field_ptr(f);
+    quote!(align.max(<#ptr as columnar_trait::ArrayPtr>::ALIGN))
+}
+
+fn fields_lanes(fields: &FieldsNamed) -> TokenStream {
+    let columnar_fields = fields.named.iter().map(|f| {
+        let ptr = field_ptr(f);
+        let align = field_align(f);
+        quote!(let lanes = lanes.max(<#ptr as columnar_trait::ArrayPtr>::lanes(#align));)
+    });
+    quote!(#(#columnar_fields)*)
+}
+
+fn fields_layout(fields: &FieldsNamed) -> TokenStream {
+    let columnar_fields = fields.named.iter().map(|f| {
+        let ptr = field_ptr(f);
+        let align = field_align(f);
+        quote! {
+            let (layout, _) = layout
+                .extend(<#ptr as columnar_trait::ArrayPtr>::layout(len, #align))
+                .unwrap();
+        }
+    });
+    quote!(#(#columnar_fields)*)
+}
+
+fn fields_from_raw_parts(fields: &FieldsNamed) -> TokenStream {
+    let columnar_fields = fields.named.iter().map(|f| {
+        let name = &f.ident;
+        let ptr = field_ptr(f);
+        let align = field_align(f);
+        quote! {
+            let (layout, offset) = layout
+                .extend(<#ptr as columnar_trait::ArrayPtr>::layout(len, #align))
+                .unwrap();
+            let #name = <#ptr as columnar_trait::ArrayPtr>::from_raw_parts(base.add(offset), len, #align);
+        }
+    });
+    quote!(#(#columnar_fields)* let _ = layout;)
+}
+
+fn base_ptr(fields: &FieldsNamed) -> TokenStream {
+    match fields.named.first() {
+        Some(f) => {
+            let name = &f.ident;
+            quote!(columnar_trait::ArrayPtr::base_ptr(&self.#name))
+        }
+        None => quote!(std::ptr::NonNull::<u8>::dangling().as_ptr()),
+    }
+}
+
+fn fields_describe(fields: &FieldsNamed) -> TokenStream {
+    let columnar_fields = fields.named.iter().map(|f| {
+        let ptr = field_ptr(f);
+        quote!(<#ptr as columnar_trait::ArrayPtr>::describe_columns(columns);)
+    });
+    quote!(#(#columnar_fields)*)
+}
+
+fn fields_dealloc(fields: &FieldsNamed) -> TokenStream {
+    let columnar_fields = fields.named.iter().map(|f| {
+        let name = &f.ident;
+        let align = field_align(f);
+        quote!(columnar_trait::ArrayPtr::dealloc(&mut self.#name, len, #align, alloc);)
+    });
+    quote!(#(#columnar_fields)*)
+}
+
+fn fields_realloc(fields: &FieldsNamed) -> TokenStream {
+    let columnar_fields = fields.named.iter().map(|f| {
+        let name = &f.ident;
+        let align = field_align(f);
+        quote!(columnar_trait::ArrayPtr::realloc(&mut self.#name, old_len, new_len, #align, alloc);)
+    });
+    quote!(#(#columnar_fields)*)
+}
+
+fn field_slices(f: &Field) -> TokenStream {
+    let ptr = field_ptr(f);
+    quote!(<#ptr as columnar_trait::ArrayPtr>::Slices<'a>)
+}
+
+fn ref_fields(fields: &FieldsNamed) -> TokenStream {
+    if fields.named.is_empty() {
+        return quote!(_lt: std::marker::PhantomData<&'a ()>,);
+    }
+    let columnar_fields = fields.named.iter().map(|f| {
+        let name = &f.ident;
+        let ptr = field_ptr(f);
+        quote!(#name: <#ptr as columnar_trait::ArrayPtr>::Ref<'a>,)
+    });
+    quote!(#(#columnar_fields)*)
+}
+
+fn ref_accessors(fields: &FieldsNamed) -> TokenStream {
+    let columnar_fields = fields.named.iter().map(|f| {
+        let name = &f.ident;
+        let vis = &f.vis;
+        let ptr = field_ptr(f);
+        let doc = format!("The `{}` field.", name.as_ref().unwrap());
+        quote! {
+            #[doc = #doc]
+            #[inline]
+            #vis fn #name(&self) -> <#ptr as columnar_trait::ArrayPtr>::Ref<'a> {
+                self.#name
+            }
+        }
+    });
+    quote!(#(#columnar_fields)*)
+}
+
+fn fields_row_ref(fields: &FieldsNamed) -> TokenStream {
+    if fields.named.is_empty() {
+        return quote!(_lt: std::marker::PhantomData,);
+    }
+    let columnar_fields = fields.named.iter().map(|f| {
+        let name = &f.ident;
+        quote!(#name: columnar_trait::ArrayPtr::row_ref(&self.#name, idx),)
+    });
+    quote!(#(#columnar_fields)*)
+}
+
+fn ref_mut_fields(fields: &FieldsNamed) -> TokenStream {
+    if fields.named.is_empty() {
+        return quote!(_lt: std::marker::PhantomData<&'a mut ()>,);
+    }
+    let columnar_fields = fields.named.iter().map(|f| {
+        let name = &f.ident;
+        let vis = &f.vis;
+        let ptr = field_ptr(f);
+        quote!(#vis #name: <#ptr as columnar_trait::ArrayPtr>::RefMut<'a>,)
+    });
+    quote!(#(#columnar_fields)*)
+}
+
+fn fields_row_mut(fields: &FieldsNamed) -> TokenStream {
+    if fields.named.is_empty() {
+        return quote!(_lt: std::marker::PhantomData,);
+    }
+    let columnar_fields = fields.named.iter().map(|f| {
+        let name = &f.ident;
+        quote!(#name: columnar_trait::ArrayPtr::row_mut(&self.#name, idx),)
+    });
+    quote!(#(#columnar_fields)*)
+}
+
+fn slices_fields(fields: &FieldsNamed) -> TokenStream {
+    if fields.named.is_empty() {
+        return quote!(_lt: std::marker::PhantomData<&'a ()>,);
+    }
+    slices_args(fields)
+}
+
+fn slices_args(fields: &FieldsNamed) -> TokenStream {
+    let columnar_fields = fields.named.iter().map(|f| {
+        let name = &f.ident;
+        let slices = field_slices(f);
+        quote!(#name: #slices,)
+    });
+    quote!(#(#columnar_fields)*)
+}
+
+/// Length of the shortest field's slices, so that slices built directly in
+/// the deriving module, bypassing `new`, are never read past their end.
+fn slices_len(fields: &FieldsNamed) -> TokenStream {
+    if fields.named.is_empty() {
+        return quote!(0);
+    }
+    let columnar_fields = fields.named.iter().map(|f| {
+        let name = &f.ident;
+        let ptr = field_ptr(f);
+        quote!(.min(<#ptr as columnar_trait::ArrayPtr>::slices_len(slices.#name)))
+    });
+    quote!(usize::MAX #(#columnar_fields)*)
+}
+
+fn slices_accessors(fields: &FieldsNamed) -> TokenStream {
+    let columnar_fields = fields.named.iter().map(|f| {
+        let name = &f.ident;
+        let vis = &f.vis;
+        let slices = field_slices(f);
+        let doc = format!("The `{}` column.", name.as_ref().unwrap());
+        quote! {
+            #[doc = #doc]
+            #[inline]
+            #vis fn #name(&self) -> #slices {
+                self.#name
+            }
+        }
+    });
+    quote!(#(#columnar_fields)*)
+}
+
+fn slices_mut_fields(fields: &FieldsNamed) -> TokenStream {
+    if fields.named.is_empty() {
+        return quote!(_lt: std::marker::PhantomData<&'a mut ()>,);
+    }
+    let columnar_fields = fields.named.iter().map(|f| {
+        let name = &f.ident;
+        let ptr = field_ptr(f);
+        quote!(#name: <#ptr as columnar_trait::ArrayPtr>::SlicesMut<'a>,)
+    });
+    quote!(#(#columnar_fields)*)
+}
+
+fn slices_mut_accessors(fields: &FieldsNamed) -> TokenStream {
+    let columnar_fields = fields.named.iter().map(|f| {
+        let name = &f.ident;
+        let vis = &f.vis;
+        let ptr = field_ptr(f);
+        let accessor = format_ident!("{}_mut", name.as_ref().unwrap());
+        let doc = format!("The `{}` column, mutably.", name.as_ref().unwrap());
+        quote! {
+            #[doc = #doc]
+            #[inline]
+            #vis fn #accessor(&mut self) -> <#ptr as columnar_trait::ArrayPtr>::SlicesMut<'_> {
+                <#ptr as columnar_trait::ArrayPtr>::reborrow_slices_mut(&mut self.#name)
+            }
+        }
+    });
+    quote!(#(#columnar_fields)*)
+}
+
+/// Borrows every column for `len` rows through `ArrayPtr::#method`.
+fn fields_slices(fields: &FieldsNamed, method: TokenStream) -> TokenStream {
+    if fields.named.is_empty() {
+        return quote!(_lt: std::marker::PhantomData,);
+    }
+    let columnar_fields = fields.named.iter().map(|f| {
+        let name = &f.ident;
+        quote!(#name: columnar_trait::ArrayPtr::#method(&self.#name, len),)
+    });
+    quote!(#(#columnar_fields)*)
+}
+
+fn fields_reborrow(fields: &FieldsNamed) -> TokenStream {
+    if fields.named.is_empty() {
+        return quote!(_lt: std::marker::PhantomData,);
+    }
+    let columnar_fields = fields.named.iter().map(|f| {
+        let name = &f.ident;
+        let ptr = field_ptr(f);
+        quote!(#name: <#ptr as columnar_trait::ArrayPtr>::reborrow_slices_mut(&mut slices.#name),)
+    });
+    quote!(#(#columnar_fields)*)
+}
+
+fn slices_len_eq(fields: &FieldsNamed) -> TokenStream {
+    let columnar_fields = fields.named.iter().map(|f| {
+        let name = &f.ident;
+        let ptr = field_ptr(f);
+        quote!(&& <#ptr as columnar_trait::ArrayPtr>::slices_len(slices.#name) == len)
+    });
+    quote!(#(#columnar_fields)*)
+}
+
+fn fields_from_slices(fields: &FieldsNamed) -> TokenStream {
+    let columnar_fields = fields.named.iter().map(|f| {
+        let name = &f.ident;
+        quote!(#name: columnar_trait::ArrayPtr::from_slices(slices.#name),)
+    });
+    quote!(#(#columnar_fields)*)
+}
+
+fn fields_offset(fields: &FieldsNamed) -> TokenStream {
+    let columnar_fields = fields.named.iter().map(|f| {
+        let name = &f.ident;
+        quote!(#name: columnar_trait::ArrayPtr::offset(&self.#name, count),)
+    });
+    quote!(#(#columnar_fields)*)
+}
+
+fn fields_copy(fields: &FieldsNamed) -> TokenStream {
+    let columnar_fields = fields.named.iter().map(|f| {
+        let name = &f.ident;
+        quote!(columnar_trait::ArrayPtr::copy_rows(&self.#name, &dst.#name, count);)
+    });
+    quote!(#(#columnar_fields)*)
+}
+
+fn fields_eq(fields: &FieldsNamed) -> TokenStream {
+    let columnar_fields = fields.named.iter().map(|f| {
+        let name = &f.ident;
+        quote!(&& columnar_trait::ArrayPtr::rows_eq(&self.#name, &other.#name, len))
+    });
+    quote!(#(#columnar_fields)*)
+}
+
+fn fields_write(fields: &FieldsNamed) -> TokenStream {
+    let columnar_fields = fields.named.iter().map(|f| {
+        let name = &f.ident;
+        quote!(columnar_trait::ArrayPtr::write(&self.#name, idx, #name);)
+    });
+    quote!(#(#columnar_fields)*)
+}
+
+fn fields_zero(fields: &FieldsNamed) -> TokenStream {
+    let columnar_fields = fields.named.iter().map(|f| {
+        let name = &f.ident;
+        quote!(columnar_trait::Zeroable::write_zeroes(&self.#name, count);)
+    });
+    quote!(#(#columnar_fields)*)
+}
+
 fn fields_chunk(fields: &FieldsNamed) -> TokenStream {
     let columnar_fields = fields.named.iter().map(|f| {
         let name = &f.ident;
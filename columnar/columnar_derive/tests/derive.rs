@@ -0,0 +1,506 @@
+#![feature(maybe_uninit_array_assume_init)]
+use columnar_derive::Columnar;
+use columnar_trait::{
+    ArrayPtr, ColumnAllocator, Global, StructOfArrays, StructOfArraysSlice, StructOfMaybeArrays,
+};
+use std::alloc::Layout;
+use std::cell::Cell;
+
+#[derive(Columnar, Debug, Clone, Copy, PartialEq)]
+#[columnar(zeroable)]
+pub struct Inner {
+    x: f32,
+    y: f32,
+}
+
+#[derive(Columnar, Debug, Clone, Copy, PartialEq)]
+#[columnar(
+    zeroable,
+    view(Tagged = [id, flag], derive(Debug, PartialEq)),
+    view(Position = [inner])
+)]
+pub struct Outer {
+    id: u64,
+    inner: Inner,
+    flag: bool,
+}
+
+#[derive(Columnar, Debug, Clone, Copy, PartialEq)]
+#[columnar(contiguous)]
+pub struct Packed {
+    a: bool,
+    inner: Inner,
+    b: f64,
+}
+
+#[derive(Columnar, Debug, Clone, Copy, PartialEq)]
+#[columnar(align = 64)]
+pub struct Aligned {
+    a: f64,
+    b: bool,
+    inner: Inner,
+}
+
+#[derive(Columnar, Debug, Clone, Copy, PartialEq)]
+#[columnar(contiguous, align = 32)]
+pub struct AlignedPacked {
+    a: bool,
+    b: u64,
+}
+
+fn outer(i: usize) -> Outer {
+    Outer {
+        id: i as u64,
+        inner: Inner {
+            x: i as f32,
+            y: -(i as f32),
+        },
+        flag: i % 2 == 0,
+    }
+}
+
+fn outer_rows(len: usize) -> StructOfArrays<OuterPtrs> {
+    (0..len).map(outer).collect()
+}
+
+#[test]
+fn drop_nested() {
+    let arrays = StructOfArrays::<OuterPtrs>::zeroed(1000);
+    assert_eq!(arrays.len(), 1000);
+    drop(arrays);
+
+    let empty = StructOfArrays::<OuterPtrs>::zeroed(0);
+    assert!(empty.is_empty());
+}
+
+#[test]
+fn clone_nested() {
+    let arrays = outer_rows(10);
+    let mut cloned = arrays.clone();
+    assert_eq!(arrays, cloned);
+    assert_eq!(cloned.row(3), Some(outer(3)));
+
+    cloned.set_row(0, outer(5));
+    assert_ne!(arrays, cloned);
+    assert_eq!(arrays.row(0), Some(outer(0)));
+    assert_ne!(arrays, outer_rows(9));
+    assert_eq!(
+        format!("{:?}", outer_rows(0)),
+        "StructOfArrays { len: 0, rows: [] }"
+    );
+}
+
+#[test]
+fn initialized_nested() {
+    let rows: Vec<_> = (0..5).map(outer).collect();
+    let arrays = StructOfArrays::<OuterPtrs>::from_rows(&rows);
+    assert_eq!(arrays.iter().collect::<Vec<_>>(), rows);
+
+    let filled = StructOfArrays::<OuterPtrs>::filled(3, outer(7));
+    assert!(filled.iter().all(|row| row == outer(7)));
+
+    let zeroed = StructOfArrays::<OuterPtrs>::zeroed(2);
+    assert_eq!(
+        zeroed.row(1),
+        Some(Outer {
+            id: 0,
+            inner: Inner { x: 0.0, y: 0.0 },
+            flag: false,
+        })
+    );
+}
+
+#[test]
+fn push_nested() {
+    let mut arrays = StructOfArrays::<OuterPtrs>::new();
+    (0..100).for_each(|i| arrays.push(outer(i)));
+    arrays.extend((100..250).map(outer));
+    assert_eq!(arrays, outer_rows(250));
+
+    arrays.truncate(10);
+    arrays.shrink_to_fit();
+    assert_eq!(arrays.capacity(), 10);
+    assert_eq!(arrays.iter().last(), Some(outer(9)));
+}
+
+#[test]
+fn slice_nested() {
+    let arrays = outer_rows(50);
+    let slice = arrays.slice(20..30);
+    assert_eq!(slice.row(0), Some(outer(20)));
+    assert!(slice.iter().eq((20..30).map(outer)));
+    assert_eq!(slice.chunk::<2>(8), Some([outer(28), outer(29)]));
+
+    let cloned = slice.clone();
+    let ids = std::thread::scope(|scope| {
+        scope
+            .spawn(move || cloned.iter().map(|row| row.id).sum::<u64>())
+            .join()
+            .unwrap()
+    });
+    assert_eq!(ids, (20..30).sum());
+}
+
+#[test]
+fn set_nested() {
+    let mut arrays = outer_rows(10);
+    arrays.set_row(2, outer(20));
+    arrays.set_chunk(8, [outer(80), outer(90)]);
+    assert_eq!(arrays.row(2), Some(outer(20)));
+    assert_eq!(arrays.row(9), Some(outer(90)));
+
+    let row = arrays.row_mut(5).unwrap();
+    *row.id = 50;
+    *row.inner.y *= 2.0;
+    assert_eq!(
+        arrays.row(5),
+        Some(Outer {
+            id: 50,
+            inner: Inner { x: 5.0, y: -10.0 },
+            flag: false,
+        })
+    );
+    assert!(arrays.row_mut(10).is_none());
+}
+
+#[test]
+fn projected_views() {
+    let arrays = outer_rows(10);
+    assert_eq!(
+        arrays.row_view::<Tagged>(3),
+        Some(Tagged { id: 3, flag: false })
+    );
+    assert_eq!(arrays.row_view::<Tagged>(10), None);
+
+    let tagged: Vec<Tagged> = arrays.iter_view().filter(|row: &Tagged| row.flag).collect();
+    assert_eq!(tagged.len(), 5);
+    assert_eq!(tagged[4], Tagged { id: 8, flag: true });
+
+    let [a, b] = arrays.chunk_view::<Position, 2>(8).unwrap();
+    assert_eq!(a.inner, outer(8).inner);
+    assert_eq!(b.inner, outer(9).inner);
+    assert!(arrays.chunk_view::<Position, 2>(9).is_none());
+}
+
+/// Implements none of the traits a view could derive.
+#[derive(Columnar, Clone, Copy)]
+pub struct Opaque {
+    v: f32,
+}
+
+#[derive(Columnar, Clone, Copy)]
+#[columnar(view(OpaqueOnly = [opaque]))]
+pub struct HoldsOpaque {
+    opaque: Opaque,
+    n: u64,
+}
+
+#[test]
+fn view_without_derives() {
+    let arrays: StructOfArrays<HoldsOpaquePtrs> = (0..4)
+        .map(|n| HoldsOpaque {
+            opaque: Opaque { v: n as f32 },
+            n,
+        })
+        .collect();
+    let [a, b] = arrays.chunk_view::<OpaqueOnly, 2>(2).unwrap();
+    assert_eq!((a.opaque.v, b.opaque.v), (2.0, 3.0));
+    assert_eq!(
+        arrays.row_view::<OpaqueOnly>(1).map(|row| row.opaque.v),
+        Some(1.0)
+    );
+}
+
+#[test]
+fn nullable_nested() {
+    let mut arrays = StructOfMaybeArrays::<OuterPtrs>::new(3);
+    arrays.set_some(0, outer(0));
+    arrays.set_some(2, outer(2));
+    assert_eq!(arrays.row(1), Some(None));
+    assert_eq!(
+        arrays.iter().collect::<Vec<_>>(),
+        [Some(outer(0)), None, Some(outer(2))]
+    );
+
+    arrays.set_some(1, outer(1));
+    let arrays = StructOfArrays::try_from(arrays).unwrap();
+    assert_eq!(arrays, outer_rows(3));
+}
+
+#[test]
+fn nullable_aligned() {
+    let row = |i| Aligned {
+        a: i as f64,
+        b: i % 2 == 0,
+        inner: Inner { x: 0.0, y: 1.0 },
+    };
+    let lanes = AlignedPtrs::lanes(64);
+    assert!(lanes > 5);
+    let maybe = || -> StructOfMaybeArrays<AlignedPtrs> {
+        (0..5).map(|i| (i != 2).then(|| row(i))).collect()
+    };
+
+    let filled = maybe().fill_nones(row(7));
+    assert_eq!(filled.capacity() % lanes, 0);
+    assert_eq!(filled.row(2), Some(row(7)));
+    let (compacted, _) = maybe().compact();
+    assert_eq!(compacted.capacity() % lanes, 0);
+
+    let mut full = maybe();
+    full.set_some(2, row(2));
+    let arrays = StructOfArrays::try_from(full).unwrap();
+    assert_eq!(arrays.capacity() % lanes, 0);
+    assert!(arrays.iter().eq((0..5).map(row)));
+}
+
+#[test]
+fn lazy_refs() {
+    let arrays = outer_rows(10);
+    let row = arrays.row_ref(3).unwrap();
+    assert_eq!(*row.id(), 3);
+    assert_eq!(*row.inner().y(), -3.0);
+    assert!(arrays.row_ref(10).is_none());
+
+    let ids: Vec<u64> = arrays
+        .iter_refs()
+        .filter(|row| *row.flag())
+        .map(|row| *row.id())
+        .collect();
+    assert_eq!(ids, [0, 2, 4, 6, 8]);
+    assert_eq!(arrays.iter_refs().len(), 10);
+}
+
+#[test]
+fn column_accessors() {
+    let mut arrays = outer_rows(4);
+    assert_eq!(arrays.columns().id(), &[0, 1, 2, 3]);
+    assert_eq!(arrays.columns().inner().y(), &[0.0, -1.0, -2.0, -3.0]);
+
+    let mut columns = arrays.columns_mut();
+    columns.id_mut().reverse();
+    columns.inner_mut().x_mut().fill(7.0);
+    assert_eq!(arrays.row(0).map(|row| row.id), Some(3));
+    assert!(arrays.iter().all(|row| row.inner.x == 7.0));
+    assert_eq!(arrays.slice(2..).columns().flag(), &[true, false]);
+}
+
+#[test]
+fn borrowed_slices() {
+    let ids = [1, 2, 3];
+    let (xs, ys) = ([0.5, 1.5, 2.5], [-0.5, -1.5, -2.5]);
+    let flags = [true, false, true];
+    let inner = InnerSlices::new(&xs, &ys).unwrap();
+    let slices = OuterSlices::new(&ids, inner, &flags).unwrap();
+    let view = StructOfArraysSlice::<OuterPtrs>::from_slices(slices);
+    assert_eq!(view.len(), 3);
+    assert_eq!(
+        view.row(1),
+        Some(Outer {
+            id: 2,
+            inner: Inner { x: 1.5, y: -1.5 },
+            flag: false,
+        })
+    );
+    assert_eq!(view.iter().filter(|row| row.flag).count(), 2);
+
+    assert!(InnerSlices::new(&xs, &ys[1..]).is_none());
+    assert!(OuterSlices::new(&ids[..2], inner, &flags[..2]).is_none());
+
+    // Built directly, skipping the length check of `new`.
+    let ragged = OuterSlices {
+        id: &ids,
+        inner: InnerSlices {
+            x: &xs,
+            y: &ys[..1],
+        },
+        flag: &flags[..2],
+    };
+    let view = StructOfArraysSlice::<OuterPtrs>::from_slices(ragged);
+    assert_eq!(view.len(), 1);
+    assert_eq!(view.row(1), None);
+}
+
+#[test]
+fn contiguous_layout() {
+    let layout = PackedPtrs::layout(10, 1);
+    assert_eq!(layout.size(), 176);
+    assert_eq!(layout.align(), 8);
+
+    let mut ptrs = PackedPtrs::new(10, 1, &Global);
+    assert_eq!(ptrs.base_ptr(), ptrs.a as *const u8);
+    assert_eq!(ptrs.inner.x as usize - ptrs.a as usize, 12);
+    assert_eq!(ptrs.inner.y as usize - ptrs.a as usize, 52);
+    assert_eq!(ptrs.b as usize - ptrs.a as usize, 96);
+    unsafe { ptrs.dealloc(10, 1, &Global) };
+}
+
+#[test]
+fn contiguous_push() {
+    let packed = |i: usize| Packed {
+        a: i % 3 == 0,
+        inner: Inner {
+            x: i as f32,
+            y: 0.5,
+        },
+        b: -(i as f64),
+    };
+    let mut arrays = StructOfArrays::<PackedPtrs>::new();
+    arrays.extend((0..100).map(packed));
+    assert_eq!(
+        arrays.iter().collect::<Vec<_>>(),
+        (0..100).map(packed).collect::<Vec<_>>()
+    );
+
+    let cloned = arrays.clone();
+    arrays.truncate(3);
+    arrays.shrink_to_fit();
+    assert_eq!(arrays.capacity(), 3);
+    assert_eq!(cloned.row(2), arrays.row(2));
+    assert_eq!(cloned.row(99), Some(packed(99)));
+}
+
+#[test]
+fn aligned_columns() {
+    assert_eq!(AlignedPtrs::lanes(AlignedPtrs::ALIGN), 64);
+    assert_eq!(AlignedPackedPtrs::lanes(AlignedPackedPtrs::ALIGN), 32);
+
+    let row = |i: usize| Aligned {
+        a: i as f64,
+        b: i % 2 == 1,
+        inner: Inner { x: 1.0, y: 2.0 },
+    };
+    let mut arrays = StructOfArrays::<AlignedPtrs>::with_capacity(3);
+    assert_eq!(arrays.capacity(), 64);
+    arrays.extend((0..65).map(row));
+    assert_eq!(arrays.capacity(), 128);
+    assert_eq!(arrays.row(64), Some(row(64)));
+
+    let ptrs = AlignedPtrs::new(5, 64, &Global);
+    for addr in [
+        ptrs.a as usize,
+        ptrs.b as usize,
+        ptrs.inner.x as usize,
+        ptrs.inner.y as usize,
+    ] {
+        assert_eq!(addr % 64, 0);
+    }
+    let mut ptrs = ptrs;
+    unsafe { ptrs.dealloc(5, 64, &Global) };
+
+    let packed = AlignedPackedPtrs::layout(33, 32);
+    assert_eq!(packed.align(), 32);
+    assert_eq!(packed.size(), 64 + 33 * 8);
+    let arrays: StructOfArrays<AlignedPackedPtrs> =
+        (0..40).map(|i| AlignedPacked { a: true, b: i }).collect();
+    assert_eq!(arrays.capacity() % 32, 0);
+    assert_eq!(arrays.iter().map(|row| row.b).sum::<u64>(), 780);
+}
+
+#[derive(Default)]
+struct Counting {
+    allocs: Cell<usize>,
+    live: Cell<usize>,
+}
+
+unsafe impl ColumnAllocator for Counting {
+    fn allocate(&self, layout: Layout) -> *mut u8 {
+        self.allocs.set(self.allocs.get() + 1);
+        self.live.set(self.live.get() + 1);
+        Global.allocate(layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: *mut u8, layout: Layout) {
+        self.live.set(self.live.get() - 1);
+        Global.deallocate(ptr, layout)
+    }
+}
+
+#[test]
+fn allocator_per_field() {
+    let counting = Counting::default();
+    let mut arrays = StructOfArrays::<OuterPtrs, _>::with_capacity_in(10, &counting);
+    assert_eq!(counting.allocs.get(), 4);
+    arrays.extend((0..10).map(outer));
+    assert_eq!(counting.allocs.get(), 4);
+    assert_eq!(arrays, outer_rows(10));
+    drop(arrays);
+    assert_eq!(counting.live.get(), 0);
+}
+
+#[test]
+fn allocator_contiguous() {
+    let counting = Counting::default();
+    let mut arrays = StructOfArrays::<PackedPtrs, _>::with_capacity_in(10, &counting);
+    assert_eq!(counting.allocs.get(), 1);
+    arrays.push(Packed {
+        a: true,
+        inner: Inner { x: 1.0, y: 2.0 },
+        b: 3.0,
+    });
+    arrays.reserve(100);
+    assert_eq!(counting.allocs.get(), 2);
+    assert_eq!(counting.live.get(), 1);
+    assert_eq!(arrays.row(0).map(|row| row.b), Some(3.0));
+    drop(arrays);
+    assert_eq!(counting.live.get(), 0);
+}
+
+#[cfg(feature = "mmap")]
+mod mapped {
+    use super::{outer_rows, Aligned, AlignedPacked, AlignedPackedPtrs, AlignedPtrs, Inner};
+    use columnar_derive::Columnar;
+    use columnar_trait::{ArrayPtr, MappedStructOfArrays, StructOfArrays};
+    use std::fmt::Debug;
+
+    #[derive(Columnar, Debug, Clone, Copy, PartialEq)]
+    pub struct Glyph {
+        code: char,
+        bold: bool,
+        pos: Inner,
+    }
+
+    fn round_trip<T: ArrayPtr>(name: &str, arrays: &StructOfArrays<T>)
+    where
+        T::Row: PartialEq + Debug,
+    {
+        let path = std::env::temp_dir().join(format!("columnar-{}-{name}", std::process::id()));
+        MappedStructOfArrays::write(arrays, std::fs::File::create(&path).unwrap()).unwrap();
+        let mapped = unsafe { MappedStructOfArrays::<T>::open(&path).unwrap() };
+        assert_eq!(mapped.len(), arrays.len());
+        assert!(mapped.as_slice().iter().eq(arrays.iter()));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn round_trip_derived() {
+        let glyphs: StructOfArrays<GlyphPtrs> = ('a'..='z')
+            .enumerate()
+            .map(|(i, code)| Glyph {
+                code,
+                bold: i % 3 == 0,
+                pos: Inner {
+                    x: i as f32,
+                    y: -(i as f32),
+                },
+            })
+            .collect();
+        round_trip("glyphs", &glyphs);
+        round_trip("outer", &outer_rows(100));
+
+        let aligned: StructOfArrays<AlignedPtrs> = (0..70)
+            .map(|i| Aligned {
+                a: i as f64,
+                b: i % 2 == 0,
+                inner: Inner { x: 0.5, y: 1.5 },
+            })
+            .collect();
+        round_trip("aligned", &aligned);
+        let packed: StructOfArrays<AlignedPackedPtrs> = (0..70)
+            .map(|i| AlignedPacked {
+                a: i % 2 == 1,
+                b: i,
+            })
+            .collect();
+        round_trip("aligned_packed", &packed);
+    }
+}
@@ -0,0 +1,202 @@
+use super::ArrayPtr;
+use std::alloc::Layout;
+
+/// A source of memory for table columns.
+///
+/// Columns are plain blocks of memory, so any arena, pool or bump allocator
+/// can back a table by implementing this trait. Zero-sized layouts never
+/// reach the allocator.
+///
+/// # Safety
+///
+/// A block returned by `allocate` must be valid for reads and writes of
+/// `layout` until it is passed back to `deallocate` or `reallocate`.
+pub unsafe trait ColumnAllocator {
+    /// Allocates a block for `layout`, returning null on failure.
+    fn allocate(&self, layout: Layout) -> *mut u8;
+
+    /// Releases a block returned by this allocator for the same `layout`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been allocated by `self` with `layout`.
+    unsafe fn deallocate(&self, ptr: *mut u8, layout: Layout);
+
+    /// Moves a block into one fitting `new_layout`, preserving the leading
+    /// bytes, and returns null on failure.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been allocated by `self` with `old_layout`, and both
+    /// layouts must share the same alignment.
+    unsafe fn reallocate(&self, ptr: *mut u8, old_layout: Layout, new_layout: Layout) -> *mut u8 {
+        let moved = self.allocate(new_layout);
+        if !moved.is_null() {
+            std::ptr::copy_nonoverlapping(ptr, moved, old_layout.size().min(new_layout.size()));
+            self.deallocate(ptr, old_layout);
+        }
+        moved
+    }
+}
+
+/// The global allocator, used by tables unless another one is given.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Global;
+
+unsafe impl ColumnAllocator for Global {
+    #[inline]
+    fn allocate(&self, layout: Layout) -> *mut u8 {
+        unsafe { std::alloc::alloc(layout) }
+    }
+
+    #[inline]
+    unsafe fn deallocate(&self, ptr: *mut u8, layout: Layout) {
+        std::alloc::dealloc(ptr, layout)
+    }
+
+    #[inline]
+    unsafe fn reallocate(&self, ptr: *mut u8, old_layout: Layout, new_layout: Layout) -> *mut u8 {
+        std::alloc::realloc(ptr, old_layout, new_layout.size())
+    }
+}
+
+unsafe impl<A: ColumnAllocator + ?Sized> ColumnAllocator for &A {
+    #[inline]
+    fn allocate(&self, layout: Layout) -> *mut u8 {
+        (**self).allocate(layout)
+    }
+
+    #[inline]
+    unsafe fn deallocate(&self, ptr: *mut u8, layout: Layout) {
+        (**self).deallocate(ptr, layout)
+    }
+
+    #[inline]
+    unsafe fn reallocate(&self, ptr: *mut u8, old_layout: Layout, new_layout: Layout) -> *mut u8 {
+        (**self).reallocate(ptr, old_layout, new_layout)
+    }
+}
+
+/// Allocates a block for `layout` from `alloc`, handing out a dangling,
+/// well-aligned pointer when the layout is zero-sized.
+pub(crate) fn alloc_layout<A: ColumnAllocator>(layout: Layout, alloc: &A) -> *mut u8 {
+    if layout.size() == 0 {
+        return std::ptr::without_provenance_mut(layout.align());
+    }
+    let ptr = alloc.allocate(layout);
+    if ptr.is_null() {
+        std::alloc::handle_alloc_error(layout);
+    }
+    ptr
+}
+
+/// Releases a block returned by [`alloc_layout`] for the same `layout`.
+pub(crate) unsafe fn dealloc_layout<A: ColumnAllocator>(ptr: *mut u8, layout: Layout, alloc: &A) {
+    if layout.size() != 0 {
+        alloc.deallocate(ptr, layout);
+    }
+}
+
+/// Moves a block returned by [`alloc_layout`] into one fitting `new_layout`.
+pub(crate) unsafe fn realloc_layout<A: ColumnAllocator>(
+    ptr: *mut u8,
+    old_layout: Layout,
+    new_layout: Layout,
+    alloc: &A,
+) -> *mut u8 {
+    if old_layout.size() == 0 || new_layout.size() == 0 {
+        dealloc_layout(ptr, old_layout, alloc);
+        return alloc_layout(new_layout, alloc);
+    }
+    let ptr = alloc.reallocate(ptr, old_layout, new_layout);
+    if ptr.is_null() {
+        std::alloc::handle_alloc_error(new_layout);
+    }
+    ptr
+}
+
+/// Allocates every column of `T` out of a single block of [`ArrayPtr::layout`].
+///
+/// This backs [`ArrayPtr::new`] for types deriving `Columnar` with
+/// `#[columnar(contiguous)]`.
+pub fn alloc_contiguous<T: ArrayPtr, A: ColumnAllocator>(len: usize, align: usize, alloc: &A) -> T {
+    let base = alloc_layout(T::layout(len, align), alloc);
+    unsafe { T::from_raw_parts(base, len, align) }
+}
+
+/// Releases columns allocated by [`alloc_contiguous`].
+///
+/// # Safety
+///
+/// `ptrs` must have been returned by `alloc_contiguous(len, align, alloc)`
+/// with the same arguments.
+pub unsafe fn dealloc_contiguous<T: ArrayPtr, A: ColumnAllocator>(
+    ptrs: &T,
+    len: usize,
+    align: usize,
+    alloc: &A,
+) {
+    dealloc_layout(ptrs.base_ptr() as *mut u8, T::layout(len, align), alloc);
+}
+
+/// Moves columns allocated by [`alloc_contiguous`] into a new block with
+/// room for `new_len` rows.
+///
+/// # Safety
+///
+/// `ptrs` must have been returned by `alloc_contiguous(old_len, align, alloc)`.
+pub unsafe fn realloc_contiguous<T: ArrayPtr, A: ColumnAllocator>(
+    ptrs: &mut T,
+    old_len: usize,
+    new_len: usize,
+    align: usize,
+    alloc: &A,
+) {
+    let moved = alloc_contiguous::<T, A>(new_len, align, alloc);
+    ptrs.copy_rows(&moved, old_len.min(new_len));
+    dealloc_contiguous(ptrs, old_len, align, alloc);
+    *ptrs = moved;
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ColumnAllocator, Global};
+    use crate::StructOfArrays;
+    use std::alloc::Layout;
+    use std::cell::Cell;
+
+    /// Counts the blocks it has handed out and not yet taken back.
+    #[derive(Default)]
+    struct Live(Cell<usize>);
+
+    unsafe impl ColumnAllocator for Live {
+        fn allocate(&self, layout: Layout) -> *mut u8 {
+            self.0.set(self.0.get() + 1);
+            Global.allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: *mut u8, layout: Layout) {
+            self.0.set(self.0.get() - 1);
+            Global.deallocate(ptr, layout)
+        }
+    }
+
+    #[test]
+    fn columns_use_allocator() {
+        let live = Live::default();
+        let mut arrays = StructOfArrays::<*const u64, _>::new_in(&live);
+        assert_eq!(live.0.get(), 0);
+
+        (0..100).for_each(|i| arrays.push(i));
+        assert_eq!(live.0.get(), 1);
+        assert_eq!(arrays.iter().sum::<u64>(), 4950);
+
+        let cloned = arrays.clone();
+        assert_eq!(live.0.get(), 2);
+        assert_eq!(cloned, arrays);
+
+        drop(arrays);
+        drop(cloned);
+        assert_eq!(live.0.get(), 0);
+    }
+}
@@ -1,8 +1,15 @@
+use super::alloc::{alloc_layout, dealloc_layout, realloc_layout, ColumnAllocator, Global};
 use super::OptionMap;
-use cache_size::{l1_cache_line_size, l1_cache_size};
+use cache_size::{l1_cache_line_size, l1_cache_size, l2_cache_size};
+use std::alloc::Layout;
+use std::fmt;
 use std::iter::IntoIterator;
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
 use std::ops::Deref;
 use std::ops::Index;
+use std::ops::{Bound, Range, RangeBounds};
+use std::sync::OnceLock;
 use std::{mem::MaybeUninit, time::Duration};
 
 mod group {
@@ -399,16 +406,177 @@ mod refs {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub struct StructOfArrays<T: ArrayPtr> {
-    pub inner: T,
-    pub len: usize,
+/// An owned table whose columns are allocated by `T` from `A` and released
+/// on drop.
+pub struct StructOfArrays<T: ArrayPtr, A: ColumnAllocator = Global> {
+    pub(crate) inner: T,
+    pub(crate) len: usize,
+    capacity: usize,
+    alloc: A,
 }
 
 impl<T: ArrayPtr> StructOfArrays<T> {
-    pub fn new(len: usize) -> Self {
-        let inner = T::new(len);
-        Self { inner, len }
+    /// Constructs an empty table without allocating any rows.
+    pub fn new() -> Self {
+        Self::new_in(Global)
+    }
+
+    /// Constructs an empty table with room for `capacity` rows.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_in(capacity, Global)
+    }
+
+    /// Allocates a table of `len` rows without initializing them.
+    ///
+    /// # Safety
+    ///
+    /// Every row must be written with [`StructOfArrays::set_row`] or
+    /// [`StructOfArrays::set_chunk`] before the table is used in any other
+    /// way. Borrowing methods such as `columns_mut` and `row_mut` count as
+    /// reads, as do cloning, comparing and iterating.
+    pub unsafe fn new_uninit(len: usize) -> Self {
+        Self::new_uninit_in(len, Global)
+    }
+
+    /// Builds a table holding a copy of every row in `rows`.
+    pub fn from_rows(rows: &[T::Row]) -> Self
+    where
+        T::Row: Clone,
+    {
+        let arrays = unsafe { Self::new_uninit(rows.len()) };
+        for (idx, row) in rows.iter().enumerate() {
+            unsafe { arrays.inner.write(idx, row.clone()) };
+        }
+        arrays
+    }
+
+    /// Builds a table of `len` copies of `row`.
+    pub fn filled(len: usize, row: T::Row) -> Self
+    where
+        T::Row: Clone,
+    {
+        let arrays = unsafe { Self::new_uninit(len) };
+        for idx in 0..len {
+            unsafe { arrays.inner.write(idx, row.clone()) };
+        }
+        arrays
+    }
+
+    /// Builds a table of `len` rows with every column zeroed.
+    pub fn zeroed(len: usize) -> Self
+    where
+        T: Zeroable,
+    {
+        let arrays = unsafe { Self::new_uninit(len) };
+        unsafe { arrays.inner.write_zeroes(len) };
+        arrays
+    }
+}
+
+impl<T: ArrayPtr, A: ColumnAllocator> StructOfArrays<T, A> {
+    const MIN_CAPACITY: usize = 8;
+
+    /// Constructs an empty table whose columns will be allocated from `alloc`.
+    pub fn new_in(alloc: A) -> Self {
+        Self::with_capacity_in(0, alloc)
+    }
+
+    /// Constructs an empty table with room for `capacity` rows allocated
+    /// from `alloc`.
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        let capacity = Self::padded(capacity);
+        Self {
+            inner: T::new(capacity, T::ALIGN, &alloc),
+            len: 0,
+            capacity,
+            alloc,
+        }
+    }
+
+    /// Allocates a table of `len` rows from `alloc` without initializing them.
+    ///
+    /// # Safety
+    ///
+    /// Every row must be written with [`StructOfArrays::set_row`] or
+    /// [`StructOfArrays::set_chunk`] before the table is used in any other
+    /// way. Borrowing methods such as `columns_mut` and `row_mut` count as
+    /// reads, as do cloning, comparing and iterating.
+    pub unsafe fn new_uninit_in(len: usize, alloc: A) -> Self {
+        let mut arrays = Self::with_capacity_in(len, alloc);
+        arrays.len = len;
+        arrays
+    }
+
+    /// The allocator backing every column.
+    #[inline]
+    pub fn allocator(&self) -> &A {
+        &self.alloc
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Appends `row`, growing every column if the table is full.
+    #[inline]
+    pub fn push(&mut self, row: T::Row) {
+        if self.len == self.capacity {
+            self.grow(1);
+        }
+        unsafe { self.inner.write(self.len, row) };
+        self.len += 1;
+    }
+
+    /// Reserves room for at least `additional` more rows.
+    pub fn reserve(&mut self, additional: usize) {
+        if self.capacity - self.len < additional {
+            self.grow(additional);
+        }
+    }
+
+    /// Shrinks every column to hold `len` rows, plus any padding needed to
+    /// fill the last lane of `T::ALIGN` bytes.
+    pub fn shrink_to_fit(&mut self) {
+        if self.capacity > Self::padded(self.len) {
+            self.resize_columns(self.len);
+        }
+    }
+
+    /// Shortens the table to `len` rows, keeping the capacity.
+    pub fn truncate(&mut self, len: usize) {
+        self.len = self.len.min(len);
+    }
+
+    fn grow(&mut self, additional: usize) {
+        let required = self.len.checked_add(additional).expect("capacity overflow");
+        let capacity = required.max(self.capacity * 2).max(Self::MIN_CAPACITY);
+        self.resize_columns(capacity);
+    }
+
+    fn resize_columns(&mut self, capacity: usize) {
+        let capacity = Self::padded(capacity);
+        unsafe {
+            self.inner
+                .realloc(self.capacity, capacity, T::ALIGN, &self.alloc)
+        };
+        self.capacity = capacity;
+    }
+
+    /// Rounds `capacity` up to a whole number of `T::ALIGN`-byte lanes in
+    /// every column, so vector loads never run past the allocation.
+    fn padded(capacity: usize) -> usize {
+        capacity.next_multiple_of(T::lanes(T::ALIGN))
     }
 
     #[inline]
@@ -420,9 +588,109 @@ impl<T: ArrayPtr> StructOfArrays<T> {
         }
     }
 
+    /// Reads `N` rows starting at `idx`, or returns `None` unless they are
+    /// all in bounds.
+    #[inline]
+    pub fn chunk<const N: usize>(&self, idx: usize) -> Option<[T::Row; N]> {
+        if chunk_fits(idx, N, self.len) {
+            unsafe { Some(self.inner.chunk(idx)) }
+        } else {
+            None
+        }
+    }
+
+    /// Reads `N` rows starting at `idx` without checking bounds.
+    ///
+    /// # Safety
+    ///
+    /// `idx + N` must not exceed [`StructOfArrays::len`].
+    #[inline]
+    pub unsafe fn chunk_unchecked<const N: usize>(&self, idx: usize) -> [T::Row; N] {
+        self.inner.chunk(idx)
+    }
+
+    /// Reads only the fields of projection `V` from the row at `idx`.
+    #[inline]
+    pub fn row_view<V: Projection<T>>(&self, idx: usize) -> Option<V> {
+        if idx < self.len {
+            unsafe { Some(V::read(&self.inner, idx)) }
+        } else {
+            None
+        }
+    }
+
+    /// Reads only the fields of projection `V` from `N` rows starting at
+    /// `idx`, or returns `None` unless they are all in bounds.
+    #[inline]
+    pub fn chunk_view<V: Projection<T>, const N: usize>(&self, idx: usize) -> Option<[V; N]> {
+        if chunk_fits(idx, N, self.len) {
+            unsafe { Some(V::read_chunk(&self.inner, idx)) }
+        } else {
+            None
+        }
+    }
+
+    /// Iterates over every row, reading only the fields of projection `V`.
+    #[inline]
+    pub fn iter_view<V: Projection<T>>(
+        &self,
+    ) -> impl Iterator<Item = V> + ExactSizeIterator + DoubleEndedIterator + '_ {
+        (0..self.len).map(|i| unsafe { V::read(&self.inner, i) })
+    }
+
+    /// Borrows every column, sized to [`StructOfArrays::len`].
+    #[inline]
+    pub fn columns(&self) -> T::Slices<'_> {
+        unsafe { self.inner.slices(self.len) }
+    }
+
+    /// Borrows every column mutably, sized to [`StructOfArrays::len`].
+    #[inline]
+    pub fn columns_mut(&mut self) -> T::SlicesMut<'_> {
+        unsafe { self.inner.slices_mut(self.len) }
+    }
+
+    /// Borrows every field of the row at `idx` mutably.
+    #[inline]
+    pub fn row_mut(&mut self, idx: usize) -> Option<T::RefMut<'_>> {
+        if idx < self.len {
+            unsafe { Some(self.inner.row_mut(idx)) }
+        } else {
+            None
+        }
+    }
+
+    /// Overwrites the row at `idx`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is out of bounds.
     #[inline]
-    pub fn chunk<const N: usize>(&self, idx: usize) -> [T::Row; N] {
-        unsafe { self.inner.chunk(idx) }
+    pub fn set_row(&mut self, idx: usize, row: T::Row) {
+        assert!(
+            idx < self.len,
+            "index {idx} out of bounds for length {}",
+            self.len
+        );
+        unsafe { self.inner.write(idx, row) }
+    }
+
+    /// Overwrites `N` rows starting at `idx`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the rows is out of bounds.
+    #[inline]
+    pub fn set_chunk<const N: usize>(&mut self, idx: usize, rows: [T::Row; N]) {
+        assert!(
+            chunk_fits(idx, N, self.len),
+            "chunk {idx}..{} out of bounds for length {}",
+            idx.saturating_add(N),
+            self.len
+        );
+        for (i, row) in rows.into_iter().enumerate() {
+            unsafe { self.inner.write(idx + i, row) }
+        }
     }
 
     #[inline]
@@ -432,63 +700,844 @@ impl<T: ArrayPtr> StructOfArrays<T> {
         (0..self.len).map(|i| unsafe { self.inner.row(i) })
     }
 
+    /// Borrows the row at `idx` without reading any of its fields yet.
+    #[inline]
+    pub fn row_ref(&self, idx: usize) -> Option<T::Ref<'_>> {
+        if idx < self.len {
+            unsafe { Some(self.inner.row_ref(idx)) }
+        } else {
+            None
+        }
+    }
+
+    /// Iterates over rows whose fields are only read when accessed, so
+    /// loops touching a few fields skip loading the others.
+    #[inline]
+    pub fn iter_refs(
+        &self,
+    ) -> impl Iterator<Item = T::Ref<'_>> + ExactSizeIterator + DoubleEndedIterator + '_ {
+        (0..self.len).map(|i| unsafe { self.inner.row_ref(i) })
+    }
+
+    /// Iterates over every row, reading `N` rows at a time and the last
+    /// `len % N` rows one by one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `N` is zero.
     #[inline]
     pub fn iter_chunked<const N: usize>(
         &self,
     ) -> impl Iterator<Item = T::Row> + DoubleEndedIterator + '_ {
-        (0..self.len / N).flat_map(|i| {
-            let chunk: [_; N] = unsafe { self.inner.chunk(i * N) };
-            chunk.into_iter()
+        let chunks = self.chunks::<N>();
+        let tail = chunks.remainder_start();
+        chunks
+            .flatten()
+            .chain((tail..self.len).map(|i| unsafe { self.inner.row(i) }))
+    }
+
+    /// Iterates over `[Row; N]` chunks, leaving the last `len % N` rows to
+    /// [`Chunks::remainder`], like [`slice::chunks_exact`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `N` is zero.
+    #[inline]
+    pub fn chunks<const N: usize>(&self) -> Chunks<'_, T, N> {
+        Chunks::new(&self.inner, self.len)
+    }
+
+    /// Number of rows per batch of [`StructOfArrays::iter_batched`].
+    ///
+    /// Sized so that a batch of every column fits in the L1 data cache, or
+    /// the L2 cache when L1 can't be detected, and rounded down to whole
+    /// cache lines of the narrowest column.
+    #[inline]
+    pub fn batch_len(&self) -> usize {
+        batch_len::<T>()
+    }
+
+    /// Iterates over consecutive batches of [`StructOfArrays::batch_len`]
+    /// rows, the last of which may be shorter.
+    #[inline]
+    pub fn iter_batched(
+        &self,
+    ) -> impl Iterator<Item = StructOfArraysSlice<'_, T>> + ExactSizeIterator + '_ {
+        let batch_len = self.batch_len();
+        (0..self.len.div_ceil(batch_len)).map(move |batch| {
+            let start = batch * batch_len;
+            let end = (start + batch_len).min(self.len);
+            unsafe { StructOfArraysSlice::new(&self.inner, start..end) }
         })
     }
+
+    /// Borrows the rows in `range` without copying them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds for `self.len()`.
+    #[inline]
+    pub fn slice(&self, range: impl RangeBounds<usize>) -> StructOfArraysSlice<'_, T> {
+        unsafe { StructOfArraysSlice::new(&self.inner, slice_range(range, self.len)) }
+    }
+}
+
+/// A borrowed range of rows, returned by [`StructOfArrays::slice`] or
+/// built over existing column slices with [`StructOfArraysSlice::from_slices`].
+pub struct StructOfArraysSlice<'a, T: ArrayPtr> {
+    inner: T,
+    len: usize,
+    _lt: PhantomData<&'a T>,
+}
+
+impl<'a, T: ArrayPtr> StructOfArraysSlice<'a, T> {
+    /// Views columns borrowed from another system without copying them.
+    ///
+    /// Columns only need the alignment of their element types, not
+    /// [`ArrayPtr::ALIGN`].
+    #[inline]
+    pub fn from_slices(slices: T::Slices<'a>) -> Self {
+        Self {
+            inner: T::from_slices(slices),
+            len: T::slices_len(slices),
+            _lt: PhantomData,
+        }
+    }
+
+    /// # Safety
+    ///
+    /// Rows `range` of `columns` must be initialized and outlive `'a`.
+    pub(crate) unsafe fn new(columns: &T, range: Range<usize>) -> Self {
+        Self {
+            inner: columns.offset(range.start),
+            len: range.len(),
+            _lt: PhantomData,
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[inline]
+    pub fn row(&self, idx: usize) -> Option<T::Row> {
+        if idx < self.len {
+            unsafe { Some(self.inner.row(idx)) }
+        } else {
+            None
+        }
+    }
+
+    /// Reads `N` rows starting at `idx`, or returns `None` unless they are
+    /// all in bounds.
+    #[inline]
+    pub fn chunk<const N: usize>(&self, idx: usize) -> Option<[T::Row; N]> {
+        if chunk_fits(idx, N, self.len) {
+            unsafe { Some(self.inner.chunk(idx)) }
+        } else {
+            None
+        }
+    }
+
+    /// Reads `N` rows starting at `idx` without checking bounds.
+    ///
+    /// # Safety
+    ///
+    /// `idx + N` must not exceed [`StructOfArraysSlice::len`].
+    #[inline]
+    pub unsafe fn chunk_unchecked<const N: usize>(&self, idx: usize) -> [T::Row; N] {
+        self.inner.chunk(idx)
+    }
+
+    #[inline]
+    pub fn iter(
+        &self,
+    ) -> impl Iterator<Item = T::Row> + ExactSizeIterator + DoubleEndedIterator + '_ {
+        (0..self.len).map(|i| unsafe { self.inner.row(i) })
+    }
+
+    /// Iterates over rows whose fields are only read when accessed.
+    #[inline]
+    pub fn iter_refs(
+        &self,
+    ) -> impl Iterator<Item = T::Ref<'a>> + ExactSizeIterator + DoubleEndedIterator + '_ {
+        (0..self.len).map(|i| unsafe { self.inner.row_ref(i) })
+    }
+
+    /// Borrows every column, sized to [`StructOfArraysSlice::len`].
+    #[inline]
+    pub fn columns(&self) -> T::Slices<'a> {
+        unsafe { self.inner.slices(self.len) }
+    }
+
+    /// Narrows the view to `range`, relative to its first row.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds for `self.len()`.
+    #[inline]
+    pub fn slice(&self, range: impl RangeBounds<usize>) -> StructOfArraysSlice<'a, T> {
+        unsafe { Self::new(&self.inner, slice_range(range, self.len)) }
+    }
+}
+
+impl<T: ArrayPtr> Clone for StructOfArraysSlice<'_, T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: unsafe { self.inner.offset(0) },
+            len: self.len,
+            _lt: PhantomData,
+        }
+    }
+}
+
+impl<T: ArrayPtr + Copy> Copy for StructOfArraysSlice<'_, T> {}
+
+// SAFETY: the view is a shared borrow of its rows, which it only reads, so
+// it can cross threads whenever `&[T::Row]` could.
+unsafe impl<T: ArrayPtr> Send for StructOfArraysSlice<'_, T> where T::Row: Sync {}
+unsafe impl<T: ArrayPtr> Sync for StructOfArraysSlice<'_, T> where T::Row: Sync {}
+
+/// Cache size assumed when neither L1 nor L2 can be detected.
+const DEFAULT_CACHE_SIZE: usize = 32 * 1024;
+/// Cache line size assumed when it can't be detected.
+const DEFAULT_CACHE_LINE_SIZE: usize = 64;
+
+/// Data cache and cache line sizes, detected once.
+fn cache_sizes() -> (usize, usize) {
+    static SIZES: OnceLock<(usize, usize)> = OnceLock::new();
+    *SIZES.get_or_init(|| {
+        let cache = l1_cache_size()
+            .or_else(l2_cache_size)
+            .unwrap_or(DEFAULT_CACHE_SIZE);
+        let line = l1_cache_line_size().unwrap_or(DEFAULT_CACHE_LINE_SIZE);
+        (cache, line)
+    })
+}
+
+fn batch_len<T: ArrayPtr>() -> usize {
+    let mut columns = Vec::new();
+    T::describe_columns(&mut columns);
+    let row_bytes = columns.iter().map(Layout::size).sum::<usize>().max(1);
+    let narrowest = columns.iter().map(Layout::size).min().unwrap_or(1).max(1);
+
+    let (cache, line) = cache_sizes();
+    let step = T::lanes(T::ALIGN).max(line / narrowest).max(1);
+    (cache / row_bytes / step).max(1) * step
+}
+
+/// An iterator over `[Row; N]` chunks of a table, returned by
+/// [`StructOfArrays::chunks`].
+pub struct Chunks<'a, T: ArrayPtr, const N: usize> {
+    columns: &'a T,
+    chunks: Range<usize>,
+    len: usize,
+}
+
+impl<'a, T: ArrayPtr, const N: usize> Chunks<'a, T, N> {
+    fn new(columns: &'a T, len: usize) -> Self {
+        assert!(N != 0, "chunk size must be non-zero");
+        Self {
+            columns,
+            chunks: 0..len / N,
+            len,
+        }
+    }
+
+    fn remainder_start(&self) -> usize {
+        self.len - self.len % N
+    }
+
+    /// The last `len % N` rows, which do not fill a whole chunk.
+    #[inline]
+    pub fn remainder(&self) -> StructOfArraysSlice<'a, T> {
+        unsafe { StructOfArraysSlice::new(self.columns, self.remainder_start()..self.len) }
+    }
+}
+
+impl<T: ArrayPtr, const N: usize> Iterator for Chunks<'_, T, N> {
+    type Item = [T::Row; N];
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let chunk = self.chunks.next()?;
+        unsafe { Some(self.columns.chunk(chunk * N)) }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.chunks.size_hint()
+    }
+}
+
+impl<T: ArrayPtr, const N: usize> DoubleEndedIterator for Chunks<'_, T, N> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let chunk = self.chunks.next_back()?;
+        unsafe { Some(self.columns.chunk(chunk * N)) }
+    }
+}
+
+impl<T: ArrayPtr, const N: usize> ExactSizeIterator for Chunks<'_, T, N> {}
+
+/// Whether the `n` rows starting at `idx` are all below `len`.
+pub(crate) fn chunk_fits(idx: usize, n: usize, len: usize) -> bool {
+    idx <= len && n <= len - idx
+}
+
+/// Resolves `range` against `len`, panicking like slice indexing when it
+/// is out of bounds.
+pub(crate) fn slice_range(range: impl RangeBounds<usize>, len: usize) -> Range<usize> {
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => start.checked_add(1).expect("range start overflows usize"),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end.checked_add(1).expect("range end overflows usize"),
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => len,
+    };
+    assert!(start <= end, "range starts at {start} but ends at {end}");
+    assert!(end <= len, "range end {end} out of bounds for length {len}");
+    start..end
+}
+
+impl<T: ArrayPtr, A: ColumnAllocator> Drop for StructOfArrays<T, A> {
+    fn drop(&mut self) {
+        unsafe { self.inner.dealloc(self.capacity, T::ALIGN, &self.alloc) }
+    }
+}
+
+// SAFETY: the table owns its columns like a `Vec<T::Row>` owns its buffer,
+// and only writes to them through `&mut self`.
+unsafe impl<T: ArrayPtr, A: ColumnAllocator + Send> Send for StructOfArrays<T, A> where T::Row: Send {}
+unsafe impl<T: ArrayPtr, A: ColumnAllocator + Sync> Sync for StructOfArrays<T, A> where T::Row: Sync {}
+
+impl<T: ArrayPtr, A: ColumnAllocator + Clone> Clone for StructOfArrays<T, A> {
+    fn clone(&self) -> Self {
+        let mut arrays = Self::with_capacity_in(self.len, self.alloc.clone());
+        unsafe { self.inner.copy_rows(&arrays.inner, self.len) };
+        arrays.len = self.len;
+        arrays
+    }
+}
+
+impl<T: ArrayPtr, A: ColumnAllocator, B: ColumnAllocator> PartialEq<StructOfArrays<T, B>>
+    for StructOfArrays<T, A>
+{
+    fn eq(&self, other: &StructOfArrays<T, B>) -> bool {
+        self.len == other.len && unsafe { self.inner.rows_eq(&other.inner, self.len) }
+    }
+}
+
+impl<T: ArrayPtr, A: ColumnAllocator> fmt::Debug for StructOfArrays<T, A>
+where
+    T::Row: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StructOfArrays")
+            .field("len", &self.len)
+            .field(
+                "rows",
+                &RowsPreview::new(self.len, |idx| unsafe { self.inner.row(idx) }),
+            )
+            .finish()
+    }
+}
+
+impl<T: ArrayPtr> Default for StructOfArrays<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: ArrayPtr, A: ColumnAllocator> Extend<T::Row> for StructOfArrays<T, A> {
+    fn extend<I: IntoIterator<Item = T::Row>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        iter.for_each(|row| self.push(row));
+    }
+}
+
+impl<T: ArrayPtr> FromIterator<T::Row> for StructOfArrays<T> {
+    fn from_iter<I: IntoIterator<Item = T::Row>>(iter: I) -> Self {
+        let mut arrays = Self::new();
+        arrays.extend(iter);
+        arrays
+    }
+}
+
+/// Formats at most `DEBUG_ROWS` leading rows of a table, reading each one
+/// with `row`.
+struct RowsPreview<F> {
+    len: usize,
+    row: F,
+}
+
+impl<F> RowsPreview<F> {
+    const DEBUG_ROWS: usize = 8;
+
+    fn new(len: usize, row: F) -> Self {
+        Self { len, row }
+    }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+impl<F, R> fmt::Debug for RowsPreview<F>
+where
+    F: Fn(usize) -> R,
+    R: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut list = f.debug_list();
+        list.entries((0..self.len.min(Self::DEBUG_ROWS)).map(&self.row));
+        if self.len > Self::DEBUG_ROWS {
+            list.finish_non_exhaustive()
+        } else {
+            list.finish()
+        }
+    }
+}
+
+/// A table whose rows may be missing, tracked by an [`OptionMap`].
 pub struct StructOfMaybeArrays<T: ArrayPtr> {
     inner: T,
     nones: OptionMap,
     len: usize,
+    capacity: usize,
 }
 
 impl<T: ArrayPtr> StructOfMaybeArrays<T> {
+    /// Constructs a table of `len` rows that are all `None`.
     pub fn new(len: usize) -> Self {
+        // Padded like a `StructOfArrays`, which these columns may become.
+        let capacity = StructOfArrays::<T>::padded(len);
         Self {
-            inner: T::new(0),
+            inner: T::new(capacity, T::ALIGN, &Global),
             nones: OptionMap::new_empty(len),
             len,
+            capacity,
         }
     }
 
-    pub fn row(&self, idx: usize) -> Option<T::Row> {
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the row at `idx`, which is `Some(None)` when it is missing,
+    /// or `None` if `idx` is out of bounds.
+    #[inline]
+    pub fn row(&self, idx: usize) -> Option<Option<T::Row>> {
         if idx < self.len {
-            None
+            Some(self.row_unchecked(idx))
         } else {
             None
         }
     }
+
+    /// Marks the row at `idx` as missing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is out of bounds.
+    #[inline]
+    pub fn set_none(&mut self, idx: usize) {
+        self.check_index(idx);
+        self.nones.set(idx, true);
+    }
+
+    /// Stores `row` at `idx`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is out of bounds.
+    #[inline]
+    pub fn set_some(&mut self, idx: usize, row: T::Row) {
+        self.check_index(idx);
+        unsafe { self.inner.write(idx, row) };
+        self.nones.set(idx, false);
+    }
+
+    #[inline]
+    pub fn iter(
+        &self,
+    ) -> impl Iterator<Item = Option<T::Row>> + ExactSizeIterator + DoubleEndedIterator + '_ {
+        (0..self.len).map(|i| self.row_unchecked(i))
+    }
+
+    /// Reads the row at `idx`, which must be in bounds.
+    #[inline]
+    fn row_unchecked(&self, idx: usize) -> Option<T::Row> {
+        if self.nones.get(idx) == Some(true) {
+            None
+        } else {
+            // Rows only become `Some` once written by `set_some`.
+            unsafe { Some(self.inner.row(idx)) }
+        }
+    }
+
+    /// Converts into a [`StructOfArrays`], replacing every `None` row with
+    /// `row`.
+    pub fn fill_nones(self, row: T::Row) -> StructOfArrays<T>
+    where
+        T::Row: Clone,
+    {
+        for idx in self.nones.iter_none_indices() {
+            unsafe { self.inner.write(idx, row.clone()) };
+        }
+        let len = self.len;
+        self.into_arrays(len)
+    }
+
+    /// Converts into a [`StructOfArrays`] of the `Some` rows, in order, and
+    /// returns the index each of them had in `self`.
+    pub fn compact(self) -> (StructOfArrays<T>, Vec<usize>) {
+        let kept: Vec<usize> = self.nones.iter_some_indices().collect();
+        for (dst, &src) in kept.iter().enumerate() {
+            if dst != src {
+                unsafe { self.inner.write(dst, self.inner.row(src)) };
+            }
+        }
+        (self.into_arrays(kept.len()), kept)
+    }
+
+    /// Converts into a [`StructOfArrays`] if no row is `None`, or hands the
+    /// table back with the error, so it can still be converted with
+    /// `fill_nones` or `compact`.
+    pub fn try_into_arrays(self) -> Result<StructOfArrays<T>, (Self, ContainsNonesError)> {
+        match self.nones.null_count() {
+            0 => {
+                let len = self.len;
+                Ok(self.into_arrays(len))
+            }
+            nones => Err((self, ContainsNonesError { nones })),
+        }
+    }
+
+    /// Hands the columns over to a table of the first `len` rows.
+    fn into_arrays(self, len: usize) -> StructOfArrays<T> {
+        // The columns change owner, so `self` must not free them.
+        let value = ManuallyDrop::new(self);
+        let inner = unsafe { std::ptr::read(&value.inner) };
+        let _nones = unsafe { std::ptr::read(&value.nones) };
+        StructOfArrays {
+            inner,
+            len,
+            capacity: value.capacity,
+            alloc: Global,
+        }
+    }
+
+    fn check_index(&self, idx: usize) {
+        assert!(
+            idx < self.len,
+            "index {idx} out of bounds for length {}",
+            self.len
+        );
+    }
 }
 
-impl<T: ArrayPtr> From<StructOfMaybeArrays<T>> for StructOfArrays<T> {
-    fn from(value: StructOfMaybeArrays<T>) -> Self {
-        assert!(!value.nones.contains_nones());
-        Self {
-            inner: value.inner,
-            len: value.len,
+impl<T: ArrayPtr> Drop for StructOfMaybeArrays<T> {
+    fn drop(&mut self) {
+        unsafe { self.inner.dealloc(self.capacity, T::ALIGN, &Global) }
+    }
+}
+
+impl<T: ArrayPtr> fmt::Debug for StructOfMaybeArrays<T>
+where
+    T::Row: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StructOfMaybeArrays")
+            .field("len", &self.len)
+            .field(
+                "rows",
+                &RowsPreview::new(self.len, |idx| self.row_unchecked(idx)),
+            )
+            .finish()
+    }
+}
+
+impl<T: ArrayPtr> FromIterator<Option<T::Row>> for StructOfMaybeArrays<T> {
+    fn from_iter<I: IntoIterator<Item = Option<T::Row>>>(iter: I) -> Self {
+        let (nones, rows) = OptionMap::from_options(iter);
+        let mut arrays = Self::new(nones.len());
+        for (idx, row) in nones.iter_some_indices().zip(rows) {
+            unsafe { arrays.inner.write(idx, row) };
         }
+        arrays.nones = nones;
+        arrays
     }
 }
 
+impl<T: ArrayPtr> TryFrom<StructOfMaybeArrays<T>> for StructOfArrays<T> {
+    type Error = ContainsNonesError;
+
+    /// Fails when the table has `None` rows, dropping it. Use
+    /// [`StructOfMaybeArrays::try_into_arrays`] to keep it instead.
+    fn try_from(value: StructOfMaybeArrays<T>) -> Result<Self, Self::Error> {
+        value.try_into_arrays().map_err(|(_, err)| err)
+    }
+}
+
+/// The error returned when converting a [`StructOfMaybeArrays`] that still
+/// has `None` rows into a [`StructOfArrays`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContainsNonesError {
+    nones: usize,
+}
+
+impl ContainsNonesError {
+    /// Number of `None` rows in the table.
+    pub fn nones(&self) -> usize {
+        self.nones
+    }
+}
+
+impl fmt::Display for ContainsNonesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "table contains {} None rows", self.nones)
+    }
+}
+
+impl std::error::Error for ContainsNonesError {}
+
 pub trait ArrayPtr {
     type Row: ArrayRow;
-    fn new(len: usize) -> Self;
+
+    /// Columns borrowed from existing slices, one per field.
+    ///
+    /// Derived as `{Row}Slices` when deriving `Columnar`.
+    type Slices<'a>: Copy;
+
+    /// Columns borrowed mutably, one per field.
+    ///
+    /// Derived as `{Row}SlicesMut` when deriving `Columnar`.
+    type SlicesMut<'a>;
+
+    /// References to every field of one row, read only when dereferenced.
+    ///
+    /// Derived as `{Row}Ref` when deriving `Columnar`.
+    type Ref<'a>: Copy;
+
+    /// Mutable references to every field of one row.
+    ///
+    /// Derived as `{Row}RefMut` when deriving `Columnar`.
+    type RefMut<'a>;
+
+    /// Minimum alignment, in bytes, of the start of every column.
+    ///
+    /// Set through `#[columnar(align = N)]` when deriving `Columnar`.
+    const ALIGN: usize = 1;
+
+    /// Number of rows that fill `align` bytes of the narrowest column.
+    ///
+    /// Capacities are rounded up to a multiple of this so that every column
+    /// ends on a whole lane.
+    fn lanes(align: usize) -> usize;
+
+    /// Allocates uninitialized columns from `alloc` with room for `len`
+    /// rows, each starting on an `align`-byte boundary.
+    fn new<A: ColumnAllocator>(len: usize, align: usize, alloc: &A) -> Self;
+
+    /// Layout of every column packed into one block, each with room for
+    /// `len` rows and aligned to `align` or its element type.
+    fn layout(len: usize, align: usize) -> Layout;
+
+    /// Points every column into a block described by [`ArrayPtr::layout`].
+    ///
+    /// # Safety
+    ///
+    /// `base` must be valid for `Self::layout(len, align)`.
+    unsafe fn from_raw_parts(base: *mut u8, len: usize, align: usize) -> Self;
+
+    /// Start of the first column, which is the start of the block for
+    /// columns created by [`ArrayPtr::from_raw_parts`].
+    fn base_ptr(&self) -> *const u8;
+
+    /// Appends the element layout of every column, in [`ArrayPtr::layout`]
+    /// order.
+    fn describe_columns(columns: &mut Vec<Layout>);
+
+    /// Borrows the first `len` rows of every column.
+    ///
+    /// # Safety
+    ///
+    /// The first `len` rows must be initialized and outlive `'a`, and must
+    /// not be mutated during `'a`.
+    unsafe fn slices<'a>(&self, len: usize) -> Self::Slices<'a>;
+
+    /// Borrows the first `len` rows of every column mutably.
+    ///
+    /// # Safety
+    ///
+    /// The first `len` rows must be initialized and outlive `'a`, and no
+    /// other reference to them may be alive during `'a`.
+    unsafe fn slices_mut<'a>(&self, len: usize) -> Self::SlicesMut<'a>;
+
+    /// Reborrows mutable columns for a shorter lifetime.
+    fn reborrow_slices_mut<'b>(slices: &'b mut Self::SlicesMut<'_>) -> Self::SlicesMut<'b>;
+
+    /// Number of rows in `slices`, which is the length of the shortest
+    /// column.
+    fn slices_len(slices: Self::Slices<'_>) -> usize;
+
+    /// Points every column at the start of its slice in `slices`.
+    fn from_slices(slices: Self::Slices<'_>) -> Self;
+
+    /// Offsets every column by `count` rows.
+    ///
+    /// # Safety
+    ///
+    /// Every column must have room for at least `count` rows.
+    unsafe fn offset(&self, count: usize) -> Self;
+
+    /// Releases the columns allocated by [`ArrayPtr::new`].
+    ///
+    /// # Safety
+    ///
+    /// `self` must have been returned by `new(len, align, alloc)` with the
+    /// same arguments, and must not be read from or deallocated again
+    /// afterwards.
+    unsafe fn dealloc<A: ColumnAllocator>(&mut self, len: usize, align: usize, alloc: &A);
+
+    /// Moves every column into an allocation with room for `new_len` rows.
+    ///
+    /// Rows below `min(old_len, new_len)` are preserved.
+    ///
+    /// # Safety
+    ///
+    /// `self` must have been allocated by `new(old_len, align, alloc)`, or
+    /// resized to `old_len` since.
+    unsafe fn realloc<A: ColumnAllocator>(
+        &mut self,
+        old_len: usize,
+        new_len: usize,
+        align: usize,
+        alloc: &A,
+    );
+
+    /// Copies the first `count` rows of every column into `dst`.
+    ///
+    /// # Safety
+    ///
+    /// Both `self` and `dst` must have room for `count` rows, and their
+    /// columns must not overlap.
+    unsafe fn copy_rows(&self, dst: &Self, count: usize);
+
+    /// Compares the first `len` rows of every column.
+    ///
+    /// # Safety
+    ///
+    /// The first `len` rows of both `self` and `other` must be initialized.
+    unsafe fn rows_eq(&self, other: &Self, len: usize) -> bool;
+
     unsafe fn row(&self, idx: usize) -> Self::Row;
     unsafe fn chunk<const N: usize>(&self, idx: usize) -> [Self::Row; N];
+
+    /// Borrows every column at `idx` without reading it.
+    ///
+    /// # Safety
+    ///
+    /// `idx` must be an initialized row that outlives `'a` and is not
+    /// mutated during `'a`.
+    unsafe fn row_ref<'a>(&self, idx: usize) -> Self::Ref<'a>;
+
+    /// Borrows every column mutably at `idx`.
+    ///
+    /// # Safety
+    ///
+    /// `idx` must be an initialized row, and no other reference to it may
+    /// be alive during `'a`.
+    unsafe fn row_mut<'a>(&self, idx: usize) -> Self::RefMut<'a>;
+
+    /// Writes `row` into every column at `idx`.
+    ///
+    /// # Safety
+    ///
+    /// `idx` must be within the allocated length of every column.
+    unsafe fn write(&self, idx: usize, row: Self::Row);
+}
+
+/// Columns for which an all-zero bit pattern is a valid row.
+///
+/// Implemented by `#[columnar(zeroable)]` when deriving `Columnar`.
+///
+/// # Safety
+///
+/// Implementors must only be made of columns whose element types accept
+/// all-zero bytes, such as the primitive columns of this crate.
+pub unsafe trait Zeroable: ArrayPtr {
+    /// Zeroes the first `count` rows of every column.
+    ///
+    /// # Safety
+    ///
+    /// Every column must have room for `count` rows.
+    unsafe fn write_zeroes(&self, count: usize);
+}
+
+/// A row type holding a subset of the fields of `T::Row`.
+///
+/// Generated by `#[columnar(view(Name = [field, ...]))]` when deriving
+/// `Columnar`, deriving only the traits listed in an optional
+/// `derive(...)` next to the name, and read through [`StructOfArrays::iter_view`] and
+/// [`StructOfArrays::chunk_view`] without touching the other columns.
+pub trait Projection<T: ArrayPtr>: Sized {
+    /// Reads the projected fields of the row at `idx`.
+    ///
+    /// # Safety
+    ///
+    /// `idx` must be an initialized row of `ptrs`.
+    unsafe fn read(ptrs: &T, idx: usize) -> Self;
+
+    /// Reads the projected fields of `N` rows starting at `idx`.
+    ///
+    /// # Safety
+    ///
+    /// Rows `idx..idx + N` must be initialized rows of `ptrs`.
+    unsafe fn read_chunk<const N: usize>(ptrs: &T, idx: usize) -> [Self; N];
 }
 
 pub trait ArrayRow {
     type Ptr: ArrayPtr;
 }
 
+fn array_layout<T>(len: usize, align: usize) -> Layout {
+    Layout::array::<T>(len).unwrap().align_to(align).unwrap()
+}
+
+fn alloc_array<T, A: ColumnAllocator>(len: usize, align: usize, alloc: &A) -> *const T {
+    alloc_layout(array_layout::<T>(len, align), alloc).cast()
+}
+
+unsafe fn dealloc_array<T, A: ColumnAllocator>(ptr: *const T, len: usize, align: usize, alloc: &A) {
+    dealloc_layout(ptr as *mut u8, array_layout::<T>(len, align), alloc)
+}
+
+unsafe fn realloc_array<T, A: ColumnAllocator>(
+    ptr: *const T,
+    old_len: usize,
+    new_len: usize,
+    align: usize,
+    alloc: &A,
+) -> *const T {
+    let old_layout = array_layout::<T>(old_len, align);
+    let new_layout = array_layout::<T>(new_len, align);
+    realloc_layout(ptr as *mut u8, old_layout, new_layout, alloc).cast()
+}
+
 unsafe fn read_array<T: Copy, const N: usize>(ptr: *const T) -> [T; N] {
     let slice: &[T] = unsafe { std::slice::from_raw_parts(ptr.cast(), N) };
     slice.try_into().unwrap_unchecked()
@@ -502,21 +1551,125 @@ macro_rules! impl_prim_array_ptr {
 
         impl ArrayPtr for *const $prim {
             type Row = $prim;
+            type Slices<'a> = &'a [$prim];
+            type SlicesMut<'a> = &'a mut [$prim];
+            type Ref<'a> = &'a $prim;
+            type RefMut<'a> = &'a mut $prim;
+
+            #[inline]
+            fn lanes(align: usize) -> usize {
+                (align / std::mem::size_of::<$prim>()).max(1)
+            }
 
-            fn new(len: usize) -> Self {
-                unsafe {
-                    std::alloc::alloc(std::alloc::Layout::array::<$prim>(len).unwrap()).cast()
-                }
+            fn new<A: ColumnAllocator>(len: usize, align: usize, alloc: &A) -> Self {
+                alloc_array(len, align, alloc)
+            }
+
+            #[inline]
+            fn layout(len: usize, align: usize) -> Layout {
+                array_layout::<$prim>(len, align)
+            }
+
+            #[inline]
+            unsafe fn from_raw_parts(base: *mut u8, _len: usize, _align: usize) -> Self {
+                base.cast()
+            }
+
+            #[inline]
+            fn base_ptr(&self) -> *const u8 {
+                self.cast()
+            }
+
+            fn describe_columns(columns: &mut Vec<Layout>) {
+                columns.push(Layout::new::<$prim>());
+            }
+
+            #[inline]
+            unsafe fn slices<'a>(&self, len: usize) -> Self::Slices<'a> {
+                std::slice::from_raw_parts(*self, len)
+            }
+
+            #[inline]
+            unsafe fn slices_mut<'a>(&self, len: usize) -> Self::SlicesMut<'a> {
+                std::slice::from_raw_parts_mut(*self as *mut $prim, len)
+            }
+
+            #[inline]
+            fn reborrow_slices_mut<'b>(slices: &'b mut Self::SlicesMut<'_>) -> Self::SlicesMut<'b> {
+                slices
+            }
+
+            #[inline]
+            fn slices_len(slices: Self::Slices<'_>) -> usize {
+                slices.len()
+            }
+
+            #[inline]
+            fn from_slices(slices: Self::Slices<'_>) -> Self {
+                slices.as_ptr()
+            }
+
+            #[inline]
+            unsafe fn offset(&self, count: usize) -> Self {
+                self.add(count)
+            }
+
+            #[inline]
+            unsafe fn dealloc<A: ColumnAllocator>(&mut self, len: usize, align: usize, alloc: &A) {
+                dealloc_array(*self, len, align, alloc)
+            }
+
+            #[inline]
+            unsafe fn realloc<A: ColumnAllocator>(
+                &mut self,
+                old_len: usize,
+                new_len: usize,
+                align: usize,
+                alloc: &A,
+            ) {
+                *self = realloc_array(*self, old_len, new_len, align, alloc)
+            }
+
+            #[inline]
+            unsafe fn copy_rows(&self, dst: &Self, count: usize) {
+                std::ptr::copy_nonoverlapping(*self, *dst as *mut $prim, count)
+            }
+
+            #[inline]
+            unsafe fn rows_eq(&self, other: &Self, len: usize) -> bool {
+                std::slice::from_raw_parts(*self, len) == std::slice::from_raw_parts(*other, len)
             }
 
             #[inline]
             unsafe fn row(&self, idx: usize) -> Self::Row {
-                self.offset(idx as isize).read()
+                self.add(idx).read()
             }
 
             #[inline]
             unsafe fn chunk<const N: usize>(&self, idx: usize) -> [Self::Row; N] {
-                read_array(self.offset(idx as isize))
+                read_array(self.add(idx))
+            }
+
+            #[inline]
+            unsafe fn row_ref<'a>(&self, idx: usize) -> Self::Ref<'a> {
+                &*self.add(idx)
+            }
+
+            #[inline]
+            unsafe fn row_mut<'a>(&self, idx: usize) -> Self::RefMut<'a> {
+                &mut *(*self as *mut $prim).add(idx)
+            }
+
+            #[inline]
+            unsafe fn write(&self, idx: usize, row: Self::Row) {
+                (*self as *mut $prim).add(idx).write(row)
+            }
+        }
+
+        unsafe impl Zeroable for *const $prim {
+            #[inline]
+            unsafe fn write_zeroes(&self, count: usize) {
+                (*self as *mut $prim).write_bytes(0, count)
             }
         }
     };
@@ -624,6 +1777,7 @@ mod test {
     //use super::Iter;
     //use super::{ColumnIndex, Sequence};
     // use std::ptr::NonNull;
+    use super::{StructOfArrays, StructOfArraysSlice, StructOfMaybeArrays};
 
     fn box_contig() -> Box<[f32]> {
         vec![0.0; 1024].into()
@@ -647,6 +1801,310 @@ mod test {
         assert_eq!(bv.len(), 1023);
     }
 
+    #[test]
+    fn drop_releases_columns() {
+        let arrays = StructOfArrays::<*const f64>::zeroed(1024);
+        assert_eq!(arrays.len(), 1024);
+        drop(arrays);
+
+        let empty = StructOfArrays::<*const u64>::zeroed(0);
+        assert_eq!(empty.row(0), None);
+        drop(empty);
+    }
+
+    #[test]
+    fn initialized_constructors() {
+        let rows = StructOfArrays::<*const i32>::from_rows(&[3, 1, 2]);
+        assert_eq!(rows.iter().collect::<Vec<_>>(), vec![3, 1, 2]);
+
+        let filled = StructOfArrays::<*const char>::filled(4, 'x');
+        assert_eq!(filled.iter().collect::<String>(), "xxxx");
+
+        let zeroed = StructOfArrays::<*const bool>::zeroed(5);
+        assert!(zeroed.iter().all(|b| !b));
+
+        let collected: StructOfArrays<*const u64> = (0..5).collect();
+        assert_eq!(collected, StructOfArrays::from_rows(&[0, 1, 2, 3, 4]));
+        assert!(StructOfArrays::<*const u64>::from_rows(&[]).is_empty());
+    }
+
+    #[test]
+    fn push_grows_columns() {
+        let mut arrays = StructOfArrays::<*const u64>::new();
+        assert_eq!(arrays.capacity(), 0);
+        for i in 0..1000 {
+            arrays.push(i);
+        }
+        assert_eq!(arrays.len(), 1000);
+        assert!(arrays.capacity() >= 1000);
+        assert_eq!(arrays, counting(1000));
+    }
+
+    #[test]
+    fn reserve_shrink_truncate() {
+        let mut arrays = StructOfArrays::<*const u64>::with_capacity(4);
+        arrays.extend(0..3);
+        assert_eq!(arrays.capacity(), 4);
+
+        arrays.reserve(10);
+        assert!(arrays.capacity() >= 13);
+        assert_eq!(arrays, counting(3));
+
+        arrays.truncate(5);
+        assert_eq!(arrays.len(), 3);
+        arrays.truncate(2);
+        assert_eq!(arrays, counting(2));
+
+        arrays.shrink_to_fit();
+        assert_eq!(arrays.capacity(), 2);
+        arrays.truncate(0);
+        arrays.shrink_to_fit();
+        assert_eq!(arrays.capacity(), 0);
+        arrays.push(7);
+        assert_eq!(arrays.row(0), Some(7));
+    }
+
+    #[test]
+    fn slice_borrows_rows() {
+        let arrays = counting(100);
+        let slice = arrays.slice(10..20);
+        assert_eq!(slice.len(), 10);
+        assert_eq!(slice.row(0), Some(10));
+        assert_eq!(slice.row(10), None);
+        assert_eq!(slice.chunk::<2>(8), Some([18, 19]));
+        assert_eq!(slice.chunk::<2>(9), None);
+        assert!(slice.iter().eq(10..20));
+        assert!(slice.slice(5..).iter().eq(15..20));
+        assert!(arrays.slice(..).iter().eq(arrays.iter()));
+        assert!(arrays.slice(100..).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "range end 101 out of bounds for length 100")]
+    fn slice_out_of_bounds() {
+        counting(100).slice(50..=100);
+    }
+
+    #[test]
+    fn slices_cross_threads() {
+        let arrays = counting(100);
+        let sums: Vec<u64> = std::thread::scope(|scope| {
+            let workers: Vec<_> = [0..50, 50..100]
+                .map(|range| {
+                    let slice = arrays.slice(range);
+                    let copy = slice;
+                    scope.spawn(move || slice.iter().sum::<u64>() + copy.len() as u64)
+                })
+                .into_iter()
+                .collect();
+            workers.into_iter().map(|w| w.join().unwrap()).collect()
+        });
+        assert_eq!(sums, [1225 + 50, 3725 + 50]);
+
+        let moved = std::thread::spawn(move || arrays.len()).join().unwrap();
+        assert_eq!(moved, 100);
+    }
+
+    #[test]
+    fn set_rows() {
+        let mut arrays = counting(10);
+        arrays.set_row(3, 30);
+        arrays.set_chunk(7, [70, 80, 90]);
+        *arrays.row_mut(0).unwrap() += 5;
+        assert_eq!(arrays.row_mut(10), None);
+        assert!(arrays.iter().eq([5, 1, 2, 30, 4, 5, 6, 70, 80, 90]));
+    }
+
+    #[test]
+    fn chunked_tail() {
+        let arrays = counting(10);
+        assert!(arrays.iter_chunked::<4>().eq(0..10));
+        assert!(arrays.iter_chunked::<4>().rev().eq((0..10).rev()));
+        assert!(arrays.iter_chunked::<16>().eq(0..10));
+        assert_eq!(arrays.iter_chunked::<5>().sum::<u64>(), 45);
+
+        let mut chunks = arrays.chunks::<4>();
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks.next(), Some([0, 1, 2, 3]));
+        assert_eq!(chunks.next_back(), Some([4, 5, 6, 7]));
+        assert_eq!(chunks.next(), None);
+        assert!(chunks.remainder().iter().eq([8, 9]));
+        assert!(arrays.chunks::<5>().remainder().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "chunk size must be non-zero")]
+    fn zero_sized_chunks() {
+        counting(10).chunks::<0>();
+    }
+
+    #[test]
+    fn row_refs() {
+        let arrays = counting(10);
+        assert_eq!(arrays.row_ref(4), Some(&4));
+        assert_eq!(arrays.row_ref(10), None);
+        assert_eq!(arrays.iter_refs().rev().next(), Some(&9));
+        assert!(arrays.slice(5..).iter_refs().copied().eq(5..10));
+    }
+
+    #[test]
+    fn column_slices() {
+        let mut arrays = counting(10);
+        arrays.columns_mut().iter_mut().for_each(|x| *x *= 2);
+        assert_eq!(arrays.columns(), &[0, 2, 4, 6, 8, 10, 12, 14, 16, 18]);
+        assert_eq!(arrays.slice(8..).columns(), &[16, 18]);
+        assert!(StructOfArrays::<*const u64>::new().columns().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "index 10 out of bounds for length 10")]
+    fn set_row_out_of_bounds() {
+        counting(10).set_row(10, 0);
+    }
+
+    #[test]
+    fn batches_cover_rows() {
+        let arrays = counting(10_000);
+        let batch_len = arrays.batch_len();
+        assert!(batch_len > 0);
+        assert_eq!(batch_len % 8, 0);
+
+        let batches: Vec<_> = arrays.iter_batched().collect();
+        assert_eq!(batches.len(), 10_000_usize.div_ceil(batch_len));
+        assert!(batches.iter().all(|batch| batch.len() <= batch_len));
+        assert!(batches.iter().flat_map(|batch| batch.iter()).eq(0..10_000));
+        assert_eq!(StructOfArrays::<*const u64>::new().iter_batched().len(), 0);
+    }
+
+    #[test]
+    fn maybe_rows() {
+        let mut arrays = StructOfMaybeArrays::<*const u64>::new(4);
+        assert_eq!(arrays.row(0), Some(None));
+        assert_eq!(arrays.row(4), None);
+
+        arrays.set_some(1, 10);
+        arrays.set_some(3, 30);
+        assert_eq!(arrays.row(1), Some(Some(10)));
+        assert!(arrays.iter().eq([None, Some(10), None, Some(30)]));
+
+        arrays.set_none(1);
+        assert_eq!(arrays.row(1), Some(None));
+        assert_eq!(arrays.iter().flatten().sum::<u64>(), 30);
+    }
+
+    #[test]
+    fn maybe_conversions() {
+        let maybe = || {
+            let mut arrays = StructOfMaybeArrays::<*const u64>::new(5);
+            arrays.set_some(1, 10);
+            arrays.set_some(4, 40);
+            arrays
+        };
+        let err = StructOfArrays::try_from(maybe()).unwrap_err();
+        assert_eq!(err.nones(), 3);
+        assert_eq!(err.to_string(), "table contains 3 None rows");
+        let boxed: Box<dyn std::error::Error + Send + Sync> = err.into();
+        assert_eq!(boxed.to_string(), "table contains 3 None rows");
+
+        let (table, err) = maybe().try_into_arrays().unwrap_err();
+        assert_eq!(err.nones(), 3);
+        assert!(table.fill_nones(7).iter().eq([7, 10, 7, 7, 40]));
+
+        assert!(maybe().fill_nones(7).iter().eq([7, 10, 7, 7, 40]));
+
+        let (arrays, kept) = maybe().compact();
+        assert!(arrays.iter().eq([10, 40]));
+        assert_eq!(kept, [1, 4]);
+
+        let mut full = StructOfMaybeArrays::<*const u64>::new(2);
+        full.set_some(0, 1);
+        full.set_some(1, 2);
+        assert_eq!(
+            StructOfArrays::try_from(full),
+            Ok(StructOfArrays::from_rows(&[1, 2]))
+        );
+    }
+
+    #[test]
+    fn maybe_from_options() {
+        let rows = (0..100).map(|i| (i % 4 != 0).then_some(i));
+        let arrays: StructOfMaybeArrays<*const u64> = rows.clone().collect();
+        assert_eq!(arrays.len(), 100);
+        assert!(arrays.iter().eq(rows));
+        assert_eq!(
+            format!("{:?}", arrays),
+            "StructOfMaybeArrays { len: 100, rows: [None, Some(1), Some(2), Some(3), None, \
+             Some(5), Some(6), Some(7), ..] }"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "index 4 out of bounds for length 4")]
+    fn maybe_set_out_of_bounds() {
+        StructOfMaybeArrays::<*const u64>::new(4).set_some(4, 0);
+    }
+
+    #[test]
+    fn chunk_bounds() {
+        let arrays = counting(10);
+        assert_eq!(arrays.chunk::<3>(7), Some([7, 8, 9]));
+        assert_eq!(arrays.chunk::<3>(8), None);
+        assert_eq!(
+            arrays.chunk::<10>(0),
+            Some(std::array::from_fn(|i| i as u64))
+        );
+        assert_eq!(arrays.chunk::<11>(0), None);
+        assert_eq!(arrays.chunk::<0>(10), Some([]));
+        assert_eq!(arrays.chunk::<0>(11), None);
+        assert_eq!(arrays.chunk::<1>(usize::MAX), None);
+        assert_eq!(unsafe { arrays.chunk_unchecked::<2>(4) }, [4, 5]);
+        assert_eq!(StructOfArrays::<*const u64>::new().chunk::<1>(0), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "chunk 8..11 out of bounds for length 10")]
+    fn set_chunk_out_of_bounds() {
+        counting(10).set_chunk(8, [0; 3]);
+    }
+
+    #[test]
+    fn slice_over_borrowed_columns() {
+        let column: Vec<u64> = (0..10).collect();
+        let view = StructOfArraysSlice::<*const u64>::from_slices(&column);
+        assert_eq!(view.len(), 10);
+        assert!(view.iter().eq(0..10));
+        assert_eq!(view.slice(8..).chunk::<2>(0), Some([8, 9]));
+    }
+
+    fn counting(len: usize) -> StructOfArrays<*const u64> {
+        (0..len as u64).collect()
+    }
+
+    #[test]
+    fn clone_copies_rows() {
+        let arrays = counting(100);
+        let cloned = arrays.clone();
+        assert_ne!(arrays.inner, cloned.inner);
+        assert_eq!(arrays, cloned);
+
+        unsafe { (cloned.inner as *mut u64).write(100) };
+        assert_eq!(arrays.row(0), Some(0));
+        assert_ne!(arrays, cloned);
+        assert_ne!(arrays, counting(99));
+    }
+
+    #[test]
+    fn debug_previews_rows() {
+        assert_eq!(
+            format!("{:?}", counting(3)),
+            "StructOfArrays { len: 3, rows: [0, 1, 2] }"
+        );
+        assert_eq!(
+            format!("{:?}", counting(100)),
+            "StructOfArrays { len: 100, rows: [0, 1, 2, 3, 4, 5, 6, 7, ..] }"
+        );
+    }
+
     // #[derive(Default, Debug, Clone, Copy, PartialEq)]
     // pub struct XStruct {
     //     a: u64,
@@ -1,13 +1,27 @@
 //mod columnar;
 //#![feature(return_position_impl_trait_in_trait)]
 #![feature(slice_as_chunks)]
+mod alloc;
 mod columnar;
+#[cfg(feature = "mmap")]
+mod mmap;
 mod optional;
 mod pointer;
 //mod sequence;
 
-//pub use columnar::{ArrayPtr, ArrayRow, StructOfArrays, StructOfMaybeArrays};
-pub use columnar::{ArrayPtr, ArrayRow, StructOfArrays};
+pub use alloc::{ColumnAllocator, Global};
+pub use columnar::{
+    ArrayPtr, ArrayRow, Chunks, ContainsNonesError, Projection, StructOfArrays,
+    StructOfArraysSlice, StructOfMaybeArrays, Zeroable,
+};
+#[cfg(feature = "mmap")]
+pub use mmap::MappedStructOfArrays;
 //pub use bitmap::BitMap;
-pub use optional::OptionMap;
+pub use optional::{OptionMap, OptionMapBuilder, OptionMapIndex};
+
+/// Support for code generated by `#[derive(Columnar)]`, not public API.
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::alloc::{alloc_contiguous, dealloc_contiguous, realloc_contiguous};
+}
 //pub use sequence::Sequence;
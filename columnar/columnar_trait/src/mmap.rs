@@ -0,0 +1,277 @@
+//! Read-only tables backed by memory-mapped files.
+//!
+//! # File layout
+//!
+//! A mapped table starts with a header of native-endian `u64` words:
+//!
+//! | offset    | field                                            |
+//! |-----------|--------------------------------------------------|
+//! | 0         | magic bytes `b"COLUMNAR"`                        |
+//! | 8         | format version, currently `1`                    |
+//! | 16        | row count                                        |
+//! | 24        | capacity, the number of rows each column holds   |
+//! | 32        | column alignment, [`ArrayPtr::ALIGN`]            |
+//! | 40        | offset of the column data from the file start    |
+//! | 48        | column count                                     |
+//! | 56 + 16i  | element size, then element alignment of column i |
+//!
+//! The header is zero-padded up to the data offset, after which the columns
+//! follow back to back exactly as described by [`ArrayPtr::layout`] for the
+//! stored capacity and alignment.
+use super::alloc::{alloc_layout, dealloc_layout};
+use super::{ArrayPtr, ColumnAllocator, Global, StructOfArrays, StructOfArraysSlice};
+use memmap2::Mmap;
+use std::fs::File;
+use std::io::{self, Write};
+use std::ops::RangeBounds;
+use std::path::Path;
+
+const MAGIC: &[u8; 8] = b"COLUMNAR";
+const VERSION: u64 = 1;
+const WORD: usize = std::mem::size_of::<u64>();
+const FIXED_WORDS: usize = 7;
+
+/// A read-only table whose columns point straight into a mapped file.
+pub struct MappedStructOfArrays<T: ArrayPtr> {
+    inner: T,
+    len: usize,
+    _map: Mmap,
+}
+
+impl<T: ArrayPtr> MappedStructOfArrays<T> {
+    /// Writes `arrays` in the layout documented in this module.
+    pub fn write<A: ColumnAllocator, W: Write>(
+        arrays: &StructOfArrays<T, A>,
+        mut writer: W,
+    ) -> io::Result<()> {
+        let capacity = arrays.len.next_multiple_of(T::lanes(T::ALIGN));
+        let layout = T::layout(capacity, T::ALIGN);
+        let mut columns = Vec::new();
+        T::describe_columns(&mut columns);
+
+        let mut header = MAGIC.to_vec();
+        let header_len = (FIXED_WORDS + 2 * columns.len()) * WORD;
+        let data_offset = header_len.next_multiple_of(layout.align());
+        let words = [VERSION, arrays.len as u64, capacity as u64, T::ALIGN as u64]
+            .into_iter()
+            .chain([data_offset as u64, columns.len() as u64])
+            .chain(
+                columns
+                    .iter()
+                    .flat_map(|column| [column.size() as u64, column.align() as u64]),
+            );
+        words.for_each(|word| header.extend_from_slice(&word.to_ne_bytes()));
+        header.resize(data_offset, 0);
+
+        // Pack the columns into one zeroed block so the padding between them
+        // is written out as well.
+        let block = alloc_layout(layout, &Global);
+        let bytes = unsafe {
+            block.write_bytes(0, layout.size());
+            arrays
+                .inner
+                .copy_rows(&T::from_raw_parts(block, capacity, T::ALIGN), arrays.len);
+            std::slice::from_raw_parts(block, layout.size())
+        };
+        let written = writer
+            .write_all(&header)
+            .and_then(|()| writer.write_all(bytes));
+        unsafe { dealloc_layout(block, layout, &Global) };
+        written
+    }
+
+    /// Maps the table stored at `path`.
+    ///
+    /// # Safety
+    ///
+    /// See [`MappedStructOfArrays::from_mmap`]. The file must also not be
+    /// modified or truncated while it is mapped.
+    pub unsafe fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path)?;
+        Self::from_mmap(Mmap::map(&file)?)
+    }
+
+    /// Validates the header of `map` against `T` and points every column
+    /// into it.
+    ///
+    /// # Safety
+    ///
+    /// Only the header is validated: the columns must hold valid values of
+    /// their element types, such as those written by
+    /// [`MappedStructOfArrays::write`].
+    pub unsafe fn from_mmap(map: Mmap) -> io::Result<Self> {
+        let word = |idx: usize| -> io::Result<u64> {
+            let bytes = map
+                .get(idx * WORD..(idx + 1) * WORD)
+                .ok_or_else(|| invalid("header is truncated"))?;
+            Ok(u64::from_ne_bytes(bytes.try_into().unwrap()))
+        };
+        let usize_word = |idx: usize| -> io::Result<usize> {
+            usize::try_from(word(idx)?).map_err(|_| invalid("header value overflows usize"))
+        };
+
+        if map.get(..MAGIC.len()) != Some(MAGIC) {
+            return Err(invalid("missing columnar magic bytes"));
+        }
+        if word(1)? != VERSION {
+            return Err(invalid(format!("unsupported version {}", word(1)?)));
+        }
+        let len = usize_word(2)?;
+        let capacity = usize_word(3)?;
+        let align = usize_word(4)?;
+        let data_offset = usize_word(5)?;
+        let column_count = usize_word(6)?;
+        if len > capacity {
+            return Err(invalid(format!("{len} rows exceed capacity {capacity}")));
+        }
+        if align != T::ALIGN {
+            return Err(invalid(format!(
+                "column alignment {align} does not match {}",
+                T::ALIGN
+            )));
+        }
+
+        let mut columns = Vec::new();
+        T::describe_columns(&mut columns);
+        if column_count != columns.len() {
+            return Err(invalid(format!(
+                "{column_count} columns stored, {} expected",
+                columns.len()
+            )));
+        }
+        for (idx, column) in columns.iter().enumerate() {
+            let size = usize_word(FIXED_WORDS + 2 * idx)?;
+            let align = usize_word(FIXED_WORDS + 2 * idx + 1)?;
+            if (size, align) != (column.size(), column.align()) {
+                return Err(invalid(format!(
+                    "column {idx} stores {size}-byte elements aligned to {align}, \
+                     expected {}-byte elements aligned to {}",
+                    column.size(),
+                    column.align()
+                )));
+            }
+        }
+
+        let header_len = (FIXED_WORDS + 2 * columns.len()) * WORD;
+        if data_offset < header_len {
+            return Err(invalid(format!(
+                "column data at {data_offset} overlaps the {header_len}-byte header"
+            )));
+        }
+
+        // `T::layout` panics on overflow, so first make sure the columns
+        // could fit in the map at all.
+        let column_bytes = columns.iter().try_fold(data_offset, |bytes, column| {
+            column.size().checked_mul(capacity)?.checked_add(bytes)
+        });
+        if column_bytes.is_none_or(|bytes| bytes > map.len()) {
+            return Err(invalid("column data is truncated"));
+        }
+
+        let layout = T::layout(capacity, align);
+        if data_offset % layout.align() != 0 || (map.as_ptr() as usize) % layout.align() != 0 {
+            return Err(invalid("column data is misaligned"));
+        }
+        if data_offset.checked_add(layout.size()) > Some(map.len()) {
+            return Err(invalid("column data is truncated"));
+        }
+        let base = map.as_ptr().add(data_offset) as *mut u8;
+        Ok(Self {
+            inner: T::from_raw_parts(base, capacity, align),
+            len,
+            _map: map,
+        })
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Borrows every row without copying it out of the map.
+    #[inline]
+    pub fn as_slice(&self) -> StructOfArraysSlice<'_, T> {
+        unsafe { StructOfArraysSlice::new(&self.inner, 0..self.len) }
+    }
+
+    /// Borrows the rows in `range` without copying them out of the map.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds for `self.len()`.
+    #[inline]
+    pub fn slice(&self, range: impl RangeBounds<usize>) -> StructOfArraysSlice<'_, T> {
+        self.as_slice().slice(range)
+    }
+}
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::MappedStructOfArrays;
+    use crate::StructOfArrays;
+    use std::io::ErrorKind;
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("columnar-{}-{name}", std::process::id()))
+    }
+
+    #[test]
+    fn round_trip() {
+        let path = temp_path("round_trip");
+        let arrays: StructOfArrays<*const u64> = (0..1000).collect();
+        MappedStructOfArrays::write(&arrays, std::fs::File::create(&path).unwrap()).unwrap();
+
+        let mapped = unsafe { MappedStructOfArrays::<*const u64>::open(&path).unwrap() };
+        assert_eq!(mapped.len(), 1000);
+        let rows = mapped.as_slice();
+        assert_eq!(rows.row(999), Some(999));
+        assert_eq!(rows.row(1000), None);
+        assert_eq!(rows.chunk::<3>(10), Some([10, 11, 12]));
+        assert_eq!(rows.chunk::<3>(998), None);
+        assert!(rows.iter().eq(arrays.iter()));
+        assert!(mapped.slice(500..).iter().eq(500..1000));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn rejects_mismatched_header() {
+        let path = temp_path("mismatched");
+        let arrays = StructOfArrays::<*const u64>::from_rows(&[1, 2, 3]);
+        MappedStructOfArrays::write(&arrays, std::fs::File::create(&path).unwrap()).unwrap();
+
+        let err = unsafe { MappedStructOfArrays::<*const f32>::open(&path) }.err();
+        assert_eq!(err.map(|err| err.kind()), Some(ErrorKind::InvalidData));
+
+        let bytes = std::fs::read(&path).unwrap();
+        let mut overlapping = bytes.clone();
+        overlapping[40..48].copy_from_slice(&0u64.to_ne_bytes());
+        std::fs::write(&path, &overlapping).unwrap();
+        let err = unsafe { MappedStructOfArrays::<*const u64>::open(&path) }.err();
+        assert_eq!(
+            err.map(|err| err.to_string()).as_deref(),
+            Some("column data at 0 overlaps the 72-byte header")
+        );
+
+        let mut huge = bytes.clone();
+        huge[24..32].copy_from_slice(&(u64::MAX / 4).to_ne_bytes());
+        for corrupted in [&bytes[..bytes.len() - 1], &huge[..]] {
+            std::fs::write(&path, corrupted).unwrap();
+            let err = unsafe { MappedStructOfArrays::<*const u64>::open(&path) }.err();
+            assert_eq!(
+                err.map(|err| err.to_string()).as_deref(),
+                Some("column data is truncated")
+            );
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+}
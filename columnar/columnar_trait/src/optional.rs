@@ -1,12 +1,69 @@
 #[cfg(feature = "bitvec")]
 use bitvec::{slice::BitSlice, view::AsBits};
-use std::ops::Deref;
 use std::rc::Rc;
 
 pub type BitContainer = usize;
 
+/// A validity bitmap storing one bit per element, set when the element is
+/// `None`.
+///
+/// Bits past `len` in the last container are always clear, and the number
+/// of set bits is kept up to date so null counts never rescan the map.
 #[derive(Debug, Clone, PartialEq)]
-pub struct OptionMap(Rc<[BitContainer]>);
+pub struct OptionMap {
+    bits: Rc<[BitContainer]>,
+    len: usize,
+    nones: usize,
+}
+
+/// Containers per `NONES_CHUNK_SIZE` bits. Scanning fixed-size chunks lets
+/// the per-container popcounts vectorize.
+const CHUNK_CONTAINERS: usize = OptionMap::NONES_CHUNK_SIZE / BitContainer::BITS as usize;
+
+/// Clears the bits past `len` in the last container.
+fn clear_tail(bits: &mut [BitContainer], len: usize) {
+    let used = len % BitContainer::BITS as usize;
+    if let (Some(last), true) = (bits.last_mut(), used != 0) {
+        *last &= BitContainer::MAX >> (BitContainer::BITS as usize - used);
+    }
+}
+
+/// Position of the set bit of `bits` preceded by `rank` others, which must
+/// exist.
+///
+/// Finds the byte holding it from the running byte counts in one multiply,
+/// as in Vigna's broadword select, then clears the lower bits in that byte.
+fn select_in_container(bits: BitContainer, rank: u32) -> u32 {
+    const ONES: u64 = u64::MAX / 0xff;
+    const HIGHS: u64 = ONES << 7;
+    let bits = bits as u64;
+    let mut bytes = bits - ((bits >> 1) & (ONES * 0x55));
+    bytes = (bytes & (ONES * 0x33)) + ((bytes >> 2) & (ONES * 0x33));
+    bytes = (bytes + (bytes >> 4)) & (ONES * 0x0f);
+    // Byte `i` counts the set bits in bytes `0..=i`.
+    let running = bytes.wrapping_mul(ONES);
+    let below = (((rank as u64 * ONES) | HIGHS) - running) & HIGHS;
+    let shift = below.count_ones() * 8;
+    let before = ((running << 8) >> shift) & 0xff;
+
+    let mut byte = (bits >> shift) & 0xff;
+    for _ in 0..rank as u64 - before {
+        byte &= byte - 1;
+    }
+    shift + byte.trailing_zeros()
+}
+
+/// Counts the set bits of `bits`, one `NONES_CHUNK_SIZE` chunk at a time.
+fn count_ones(bits: &[BitContainer]) -> usize {
+    let chunk_ones = |chunk: &[BitContainer]| {
+        chunk
+            .iter()
+            .map(|bits| bits.count_ones() as usize)
+            .sum::<usize>()
+    };
+    let (chunks, rem) = bits.as_chunks::<CHUNK_CONTAINERS>();
+    chunks.iter().map(|chunk| chunk_ones(chunk)).sum::<usize>() + chunk_ones(rem)
+}
 
 impl OptionMap {
     pub const NONES_CHUNK_SIZE: usize = 2560;
@@ -21,7 +78,11 @@ impl OptionMap {
     /// let mut options = OptionMap::new_full(1024);
     /// ```
     pub fn new_full(len: usize) -> Self {
-        Self(vec![0; len].into())
+        Self {
+            bits: vec![0; Self::containers(len)].into(),
+            len,
+            nones: 0,
+        }
     }
 
     /// Constructs a new `OptionMap` where every element is `None`.
@@ -33,19 +94,432 @@ impl OptionMap {
     /// let mut options = OptionMap::new_empty(1024);
     /// ```
     pub fn new_empty(len: usize) -> Self {
-        Self(vec![1; len].into())
+        let mut bits = vec![BitContainer::MAX; Self::containers(len)];
+        clear_tail(&mut bits, len);
+        Self {
+            bits: bits.into(),
+            len,
+            nones: len,
+        }
     }
 
-    /// Constructs a new `OptionMap` using the provided BitContainer slice.
+    /// Constructs a new `OptionMap` over already packed containers, holding
+    /// `STORAGE_BITS` elements each.
     ///
     /// # Examples
     ///
     /// ```
     /// use columnar_trait::OptionMap;
     /// let mut options = OptionMap::from_slice(&[0; 10]);
+    /// assert_eq!(options.len(), 10 * OptionMap::STORAGE_BITS as usize);
     /// ```
     pub fn from_slice(slice: &[BitContainer]) -> Self {
-        Self(slice.into())
+        Self {
+            bits: slice.into(),
+            len: slice.len() * Self::STORAGE_BITS as usize,
+            nones: count_ones(slice),
+        }
+    }
+
+    /// Splits `options` into a map of which are `None` and the values of
+    /// those that are `Some`, in order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use columnar_trait::OptionMap;
+    /// let (options, values) = OptionMap::from_options([Some(1), None, Some(3)]);
+    /// assert!(options.iter().eq([false, true, false]));
+    /// assert_eq!(values, [1, 3]);
+    /// ```
+    pub fn from_options<T>(options: impl IntoIterator<Item = Option<T>>) -> (Self, Vec<T>) {
+        let options = options.into_iter();
+        let mut builder = OptionMapBuilder::with_capacity(options.size_hint().0);
+        let mut values = Vec::with_capacity(options.size_hint().0);
+        for option in options {
+            builder.append(option.is_none());
+            values.extend(option);
+        }
+        (builder.finish(), values)
+    }
+
+    /// Number of containers needed for `len` elements.
+    fn containers(len: usize) -> usize {
+        len.div_ceil(Self::STORAGE_BITS as usize)
+    }
+
+    /// Container index and bit mask of element `idx`.
+    fn position(idx: usize) -> (usize, BitContainer) {
+        let bits = Self::STORAGE_BITS as usize;
+        (idx / bits, 1 << (idx % bits))
+    }
+
+    /// Number of elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use columnar_trait::OptionMap;
+    /// assert_eq!(OptionMap::new_empty(100).len(), 100);
+    /// ```
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the bit of element `idx`, which is `true` when it is `None`,
+    /// or `None` if `idx` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use columnar_trait::OptionMap;
+    /// let options = OptionMap::new_empty(3);
+    /// assert_eq!(options.get(2), Some(true));
+    /// assert_eq!(options.get(3), None);
+    /// ```
+    #[inline]
+    pub fn get(&self, idx: usize) -> Option<bool> {
+        if idx < self.len {
+            let (container, mask) = Self::position(idx);
+            Some(self.bits[container] & mask != 0)
+        } else {
+            None
+        }
+    }
+
+    /// Marks element `idx` as `None` when `none` is set, or as `Some`
+    /// otherwise. The containers are copied first if they are shared.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use columnar_trait::OptionMap;
+    /// let mut options = OptionMap::new_full(3);
+    /// options.set(1, true);
+    /// assert_eq!(options.get(1), Some(true));
+    /// ```
+    #[inline]
+    pub fn set(&mut self, idx: usize, none: bool) {
+        assert!(
+            idx < self.len,
+            "index {idx} out of bounds for length {}",
+            self.len
+        );
+        let (container, mask) = Self::position(idx);
+        if (self.bits[container] & mask != 0) == none {
+            return;
+        }
+        let bits = &mut Rc::make_mut(&mut self.bits)[container];
+        if none {
+            *bits |= mask;
+            self.nones += 1;
+        } else {
+            *bits &= !mask;
+            self.nones -= 1;
+        }
+    }
+
+    /// Counts the `None` elements by scanning the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use columnar_trait::OptionMap;
+    /// let mut options = OptionMap::new_full(100);
+    /// options.set(7, true);
+    /// assert_eq!(options.count_nones(), 1);
+    /// ```
+    pub fn count_nones(&self) -> usize {
+        count_ones(&self.bits)
+    }
+
+    /// Number of `None` elements, kept up to date by [`OptionMap::set`]
+    /// instead of rescanning the map like [`OptionMap::count_nones`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use columnar_trait::OptionMap;
+    /// let mut options = OptionMap::new_empty(100);
+    /// options.set(7, false);
+    /// assert_eq!(options.null_count(), 99);
+    /// ```
+    #[inline]
+    pub fn null_count(&self) -> usize {
+        self.nones
+    }
+
+    /// Iterates over the bit of every element, `true` when it is `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use columnar_trait::OptionMap;
+    /// let mut options = OptionMap::new_full(3);
+    /// options.set(0, true);
+    /// assert!(options.iter().eq([true, false, false]));
+    /// ```
+    pub fn iter(
+        &self,
+    ) -> impl Iterator<Item = bool> + ExactSizeIterator + DoubleEndedIterator + '_ {
+        (0..self.len).map(|idx| {
+            let (container, mask) = Self::position(idx);
+            self.bits[container] & mask != 0
+        })
+    }
+
+    /// Iterates over the indices of the `Some` elements in ascending order,
+    /// skipping whole containers that hold none of them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use columnar_trait::OptionMap;
+    /// let mut options = OptionMap::new_empty(100);
+    /// options.set(3, false);
+    /// options.set(70, false);
+    /// assert!(options.iter_some_indices().eq([3, 70]));
+    /// ```
+    pub fn iter_some_indices(&self) -> impl Iterator<Item = usize> + '_ {
+        self.indices(|bits| !bits)
+    }
+
+    /// Iterates over the indices of the `None` elements in ascending order,
+    /// skipping whole containers that hold none of them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use columnar_trait::OptionMap;
+    /// let mut options = OptionMap::new_full(100);
+    /// options.set(3, true);
+    /// options.set(70, true);
+    /// assert!(options.iter_none_indices().eq([3, 70]));
+    /// ```
+    pub fn iter_none_indices(&self) -> impl Iterator<Item = usize> + '_ {
+        self.indices(|bits| bits)
+    }
+
+    /// Yields the position of every set bit of the containers mapped by
+    /// `select`, lowest first.
+    fn indices(
+        &self,
+        select: impl Fn(BitContainer) -> BitContainer + 'static,
+    ) -> impl Iterator<Item = usize> + '_ {
+        let len = self.len;
+        let bits = Self::STORAGE_BITS as usize;
+        (self.bits.iter().enumerate())
+            .flat_map(move |(word, &container)| {
+                let mut set = select(container);
+                std::iter::from_fn(move || {
+                    let bit = set.trailing_zeros() as usize;
+                    set &= set.checked_sub(1)?;
+                    Some(word * bits + bit)
+                })
+            })
+            // `!bits` sets the clear bits past `len` too.
+            .take_while(move |&idx| idx < len)
+    }
+
+    /// Iterates over the maximal runs of elements that are all `Some` or
+    /// all `None`, as `(start, len, is_some)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use columnar_trait::OptionMap;
+    /// let mut options = OptionMap::new_full(10);
+    /// options.set(4, true);
+    /// options.set(5, true);
+    /// let runs: Vec<_> = options.iter_runs().collect();
+    /// assert_eq!(runs, [(0, 4, true), (4, 2, false), (6, 4, true)]);
+    /// ```
+    pub fn iter_runs(&self) -> impl Iterator<Item = (usize, usize, bool)> + '_ {
+        let mut start = 0;
+        std::iter::from_fn(move || {
+            if start == self.len {
+                return None;
+            }
+            let none = self.get(start) == Some(true);
+            let end = self.run_end(start, none);
+            let run = (start, end - start, !none);
+            start = end;
+            Some(run)
+        })
+    }
+
+    /// Index of the first element at or after `start` whose bit is not
+    /// `none`, or `len` if there is none.
+    fn run_end(&self, start: usize, none: bool) -> usize {
+        let bits = Self::STORAGE_BITS as usize;
+        let flip = if none { BitContainer::MAX } else { 0 };
+        let mut word = start / bits;
+        let mut differs = (self.bits[word] ^ flip) & (BitContainer::MAX << (start % bits));
+        while differs == 0 {
+            word += 1;
+            match self.bits.get(word) {
+                Some(&container) => differs = container ^ flip,
+                None => return self.len,
+            }
+        }
+        // The clear bits past `len` end a run of `None`s there.
+        (word * bits + differs.trailing_zeros() as usize).min(self.len)
+    }
+
+    /// The `Some` bits of container `word`, leaving those past `len` clear.
+    fn some_bits(&self, word: usize) -> BitContainer {
+        let mut bits = !self.bits[word];
+        if word + 1 == self.bits.len() {
+            clear_tail(std::slice::from_mut(&mut bits), self.len);
+        }
+        bits
+    }
+
+    /// Combines the `None` bits of `self` and `other` with `op`, one
+    /// container at a time.
+    fn zip_with(
+        &self,
+        other: &OptionMap,
+        op: impl Fn(BitContainer, BitContainer) -> BitContainer,
+    ) -> OptionMap {
+        self.check_len(other);
+        let bits: Rc<[BitContainer]> = (self.bits.iter().zip(other.bits.iter()))
+            .map(|(&a, &b)| op(a, b))
+            .collect();
+        let nones = count_ones(&bits);
+        OptionMap {
+            bits,
+            len: self.len,
+            nones,
+        }
+    }
+
+    /// Like [`OptionMap::zip_with`], but overwrites `self`, copying the
+    /// containers first only if they are shared.
+    fn zip_with_in_place(
+        &mut self,
+        other: &OptionMap,
+        op: impl Fn(BitContainer, BitContainer) -> BitContainer,
+    ) {
+        self.check_len(other);
+        let bits = Rc::make_mut(&mut self.bits);
+        for (a, &b) in bits.iter_mut().zip(other.bits.iter()) {
+            *a = op(*a, b);
+        }
+        self.nones = count_ones(bits);
+    }
+
+    fn check_len(&self, other: &OptionMap) {
+        assert_eq!(
+            self.len, other.len,
+            "OptionMap lengths differ: {} and {}",
+            self.len, other.len
+        );
+    }
+
+    /// Marks an element `None` where it is `None` in both maps.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the maps have different lengths.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use columnar_trait::OptionMap;
+    /// let mut a = OptionMap::new_full(3);
+    /// let mut b = OptionMap::new_full(3);
+    /// a.set(0, true);
+    /// a.set(1, true);
+    /// b.set(0, true);
+    /// assert!(a.and(&b).iter().eq([true, false, false]));
+    /// ```
+    pub fn and(&self, other: &OptionMap) -> OptionMap {
+        self.zip_with(other, |a, b| a & b)
+    }
+
+    /// Marks an element `None` where it is `None` in either map.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the maps have different lengths.
+    pub fn or(&self, other: &OptionMap) -> OptionMap {
+        self.zip_with(other, |a, b| a | b)
+    }
+
+    /// Marks an element `None` where it is `None` in exactly one map.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the maps have different lengths.
+    pub fn xor(&self, other: &OptionMap) -> OptionMap {
+        self.zip_with(other, |a, b| a ^ b)
+    }
+
+    /// Marks an element `None` where it is `None` in `self` but not in
+    /// `other`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the maps have different lengths.
+    pub fn and_not(&self, other: &OptionMap) -> OptionMap {
+        self.zip_with(other, |a, b| a & !b)
+    }
+
+    /// Swaps `None` and `Some` for every element.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use columnar_trait::OptionMap;
+    /// assert_eq!(OptionMap::new_full(100).not(), OptionMap::new_empty(100));
+    /// ```
+    pub fn not(&self) -> OptionMap {
+        let mut bits: Vec<BitContainer> = self.bits.iter().map(|&bits| !bits).collect();
+        clear_tail(&mut bits, self.len);
+        OptionMap {
+            bits: bits.into(),
+            len: self.len,
+            nones: self.len - self.nones,
+        }
+    }
+
+    /// In-place [`OptionMap::and`].
+    pub fn and_in_place(&mut self, other: &OptionMap) {
+        self.zip_with_in_place(other, |a, b| a & b)
+    }
+
+    /// In-place [`OptionMap::or`].
+    pub fn or_in_place(&mut self, other: &OptionMap) {
+        self.zip_with_in_place(other, |a, b| a | b)
+    }
+
+    /// In-place [`OptionMap::xor`].
+    pub fn xor_in_place(&mut self, other: &OptionMap) {
+        self.zip_with_in_place(other, |a, b| a ^ b)
+    }
+
+    /// In-place [`OptionMap::and_not`].
+    pub fn and_not_in_place(&mut self, other: &OptionMap) {
+        self.zip_with_in_place(other, |a, b| a & !b)
+    }
+
+    /// In-place [`OptionMap::not`].
+    pub fn not_in_place(&mut self) {
+        let bits = Rc::make_mut(&mut self.bits);
+        bits.iter_mut().for_each(|bits| *bits = !*bits);
+        clear_tail(bits, self.len);
+        self.nones = self.len - self.nones;
     }
 
     #[cfg(feature = "bitvec")]
@@ -61,7 +535,7 @@ impl OptionMap {
     /// assert_eq!(*bits.get(0).unwrap().as_ref(), false);
     /// ```
     pub fn as_bits(&self) -> &BitSlice {
-        self.0.as_bits()
+        &self.bits.as_bits()[..self.len]
     }
 
     #[inline]
@@ -75,12 +549,7 @@ impl OptionMap {
     /// assert!(!OptionMap::new_full(OptionMap::NONES_CHUNK_SIZE + 1).contains_nones());
     /// ```
     pub fn contains_nones(&self) -> bool {
-        let bytes: &[u8] = unsafe { std::mem::transmute(self.0.deref()) };
-        let (chunks, rem) = bytes.as_chunks::<2560>();
-        rem.into_iter().sum::<u8>() != 0
-            || chunks
-                .into_iter()
-                .any(|chunk| chunk.into_iter().sum::<u8>() != 0)
+        self.nones != 0
     }
 
     // pub fn storage_len(&self) -> usize {
@@ -96,6 +565,271 @@ impl AsRef<BitSlice> for OptionMap {
     }
 }
 
+/// Collects one bit per element, `true` marking a `None`.
+///
+/// # Examples
+///
+/// ```
+/// use columnar_trait::OptionMap;
+/// let options: OptionMap = [false, true, false].into_iter().collect();
+/// assert_eq!(options.get(1), Some(true));
+/// ```
+impl FromIterator<bool> for OptionMap {
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Self {
+        let iter = iter.into_iter();
+        let mut builder = OptionMapBuilder::with_capacity(iter.size_hint().0);
+        builder.extend(iter);
+        builder.finish()
+    }
+}
+
+/// A rank/select index over an [`OptionMap`], mapping between the index of
+/// an element and its index among the `Some` elements.
+///
+/// This is what a table storing only its `Some` rows, back to back, needs
+/// to find the stored row of an element and back. The index counts the
+/// `Some`s before every block of containers, adding about a third of the
+/// map's size, and samples every `SELECT_SAMPLE`th `Some`. Samples spread
+/// over more than `SPARSE_BLOCKS` blocks store the position of each of
+/// their `Some`s instead, which costs at most as much as the bits they
+/// span.
+///
+/// # Examples
+///
+/// ```
+/// use columnar_trait::{OptionMap, OptionMapIndex};
+/// let (options, values) = OptionMap::from_options([None, Some('a'), None, Some('b')]);
+/// let index = OptionMapIndex::new(options);
+/// assert_eq!(values[index.rank(3)], 'b');
+/// assert_eq!(index.select(1), Some(3));
+/// ```
+#[derive(Debug, Clone)]
+pub struct OptionMapIndex {
+    options: OptionMap,
+    /// `Some`s before each block of `BLOCK_CONTAINERS` containers.
+    blocks: Box<[usize]>,
+    /// `Some`s before each container, counted from the start of its block.
+    containers: Box<[u16]>,
+    /// Where to find each run of `SELECT_SAMPLE` `Some`s.
+    samples: Box<[Sample]>,
+    /// Positions of the `Some`s in sparse samples.
+    positions: Box<[usize]>,
+}
+
+/// A run of `SELECT_SAMPLE` consecutive `Some`s, or fewer for the last.
+#[derive(Debug, Clone, Copy)]
+enum Sample {
+    /// Spread over the blocks `first..=last`, no more than `SPARSE_BLOCKS`
+    /// apart.
+    Dense { first: usize, last: usize },
+    /// Spread too far to search, so stored at `positions[start..]`.
+    Sparse { start: usize },
+}
+
+impl OptionMapIndex {
+    pub const BLOCK_CONTAINERS: usize = 8;
+    pub const SELECT_SAMPLE: usize = 512;
+    pub const SPARSE_BLOCKS: usize = 64;
+
+    /// Indexes `options`, which it keeps for reading single containers.
+    pub fn new(options: OptionMap) -> Self {
+        let words = options.bits.len();
+        let mut blocks = Vec::with_capacity(words.div_ceil(Self::BLOCK_CONTAINERS));
+        let mut containers = Vec::with_capacity(words);
+        let mut somes = 0;
+        for word in 0..words {
+            if word % Self::BLOCK_CONTAINERS == 0 {
+                blocks.push(somes);
+            }
+            containers.push((somes - blocks[blocks.len() - 1]) as u16);
+            somes += options.some_bits(word).count_ones() as usize;
+        }
+
+        let (samples, positions) = Self::sample(&options, somes);
+        Self {
+            options,
+            blocks: blocks.into(),
+            containers: containers.into(),
+            samples,
+            positions,
+        }
+    }
+
+    /// Samples the `somes` `Some`s of `options`, returning the samples and
+    /// the positions stored for the sparse ones.
+    fn sample(options: &OptionMap, somes: usize) -> (Box<[Sample]>, Box<[usize]>) {
+        let block_bits = Self::BLOCK_CONTAINERS * OptionMap::STORAGE_BITS as usize;
+        let mut samples = Vec::with_capacity(somes.div_ceil(Self::SELECT_SAMPLE));
+        let mut positions = Vec::new();
+        let mut indices = options.iter_some_indices();
+        while let Some(first) = indices.next() {
+            let sample: Vec<usize> = std::iter::once(first)
+                .chain(indices.by_ref().take(Self::SELECT_SAMPLE - 1))
+                .collect();
+            let (first, last) = (first / block_bits, sample[sample.len() - 1] / block_bits);
+            samples.push(if last - first <= Self::SPARSE_BLOCKS {
+                Sample::Dense { first, last }
+            } else {
+                let start = positions.len();
+                positions.extend(sample);
+                Sample::Sparse { start }
+            });
+        }
+        (samples.into(), positions.into())
+    }
+
+    /// The indexed map.
+    #[inline]
+    pub fn options(&self) -> &OptionMap {
+        &self.options
+    }
+
+    /// Returns the indexed map, dropping the index.
+    pub fn into_options(self) -> OptionMap {
+        self.options
+    }
+
+    /// Number of `Some` elements before `idx`, which is also the index
+    /// among the `Some`s of element `idx` when it is one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is greater than `len`.
+    #[inline]
+    pub fn rank(&self, idx: usize) -> usize {
+        let len = self.options.len;
+        assert!(idx <= len, "index {idx} out of bounds for length {len}");
+        if idx == len {
+            return len - self.options.nones;
+        }
+        let (word, mask) = OptionMap::position(idx);
+        let before = self.options.some_bits(word) & (mask - 1);
+        self.blocks[word / Self::BLOCK_CONTAINERS]
+            + self.containers[word] as usize
+            + before.count_ones() as usize
+    }
+
+    /// Index of the `Some` element preceded by `rank` others, or `None` if
+    /// there are no more than `rank` of them.
+    ///
+    /// Takes constant time: a sparse sample is read directly, and a dense
+    /// one binary searches at most `SPARSE_BLOCKS + 1` blocks and then the
+    /// containers of one block.
+    pub fn select(&self, rank: usize) -> Option<usize> {
+        if rank >= self.options.len - self.options.nones {
+            return None;
+        }
+        let (first, last) = match self.samples[rank / Self::SELECT_SAMPLE] {
+            Sample::Dense { first, last } => (first, last),
+            Sample::Sparse { start } => {
+                return Some(self.positions[start + rank % Self::SELECT_SAMPLE]);
+            }
+        };
+        let block = first + self.blocks[first..=last].partition_point(|&somes| somes <= rank) - 1;
+
+        let rank = rank - self.blocks[block];
+        let start = block * Self::BLOCK_CONTAINERS;
+        let end = (start + Self::BLOCK_CONTAINERS).min(self.containers.len());
+        let word = start
+            + self.containers[start..end].partition_point(|&somes| somes as usize <= rank)
+            - 1;
+
+        let rank = (rank - self.containers[word] as usize) as u32;
+        let bit = select_in_container(self.options.some_bits(word), rank);
+        Some(word * OptionMap::STORAGE_BITS as usize + bit as usize)
+    }
+}
+
+/// Builds an [`OptionMap`] one element at a time.
+///
+/// # Examples
+///
+/// ```
+/// use columnar_trait::OptionMapBuilder;
+/// let mut builder = OptionMapBuilder::new();
+/// builder.append(false);
+/// builder.append_n(true, 100);
+/// let options = builder.finish();
+/// assert_eq!(options.len(), 101);
+/// assert_eq!(options.null_count(), 100);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct OptionMapBuilder {
+    bits: Vec<BitContainer>,
+    len: usize,
+    nones: usize,
+}
+
+impl OptionMapBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Constructs a builder with room for `len` elements.
+    pub fn with_capacity(len: usize) -> Self {
+        Self {
+            bits: Vec::with_capacity(OptionMap::containers(len)),
+            ..Self::default()
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Appends an element, which is `None` when `none` is `true`.
+    #[inline]
+    pub fn append(&mut self, none: bool) {
+        let (word, mask) = OptionMap::position(self.len);
+        if word == self.bits.len() {
+            self.bits.push(0);
+        }
+        if none {
+            self.bits[word] |= mask;
+            self.nones += 1;
+        }
+        self.len += 1;
+    }
+
+    /// Appends `n` elements that are all `None` when `none` is `true`,
+    /// filling whole containers at a time.
+    pub fn append_n(&mut self, none: bool, n: usize) {
+        let len = self.len + n;
+        let fill = if none { BitContainer::MAX } else { 0 };
+        self.bits.resize(OptionMap::containers(len), fill);
+        if none {
+            let used = self.len % OptionMap::STORAGE_BITS as usize;
+            if used != 0 {
+                self.bits[self.len / OptionMap::STORAGE_BITS as usize] |= BitContainer::MAX << used;
+            }
+            clear_tail(&mut self.bits, len);
+            self.nones += n;
+        }
+        self.len = len;
+    }
+
+    /// Finishes the map of the elements appended so far.
+    pub fn finish(self) -> OptionMap {
+        OptionMap {
+            bits: self.bits.into(),
+            len: self.len,
+            nones: self.nones,
+        }
+    }
+}
+
+impl Extend<bool> for OptionMapBuilder {
+    fn extend<I: IntoIterator<Item = bool>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|none| self.append(none));
+    }
+}
+
 //impl Deref for OptionMap {
 //    type Target = Rc<[BitContainer]>;
 //    fn deref(&self) -> &Self::Target {
@@ -106,11 +840,256 @@ impl AsRef<BitSlice> for OptionMap {
 #[cfg(test)]
 mod test {
 
+    use super::{select_in_container, BitContainer, OptionMap, OptionMapBuilder, OptionMapIndex};
+
     #[test]
     fn test_ff() {
         assert_eq!(1, 1);
     }
 
+    #[test]
+    fn packs_bits() {
+        let bits = OptionMap::STORAGE_BITS as usize;
+        for len in [0, 1, bits - 1, bits, bits + 1, 3 * bits + 5] {
+            let empty = OptionMap::new_empty(len);
+            assert_eq!(empty.len(), len);
+            assert_eq!(empty.count_nones(), len);
+            assert!(empty.iter().all(|none| none));
+            assert_eq!(OptionMap::new_full(len).count_nones(), 0);
+        }
+
+        let mut options = OptionMap::new_full(2 * bits + 3);
+        let shared = options.clone();
+        options.set(bits, true);
+        options.set(2 * bits + 2, true);
+        options.set(bits, false);
+        assert_eq!(options.count_nones(), 1);
+        assert_eq!(options.get(2 * bits + 2), Some(true));
+        assert_eq!(options.get(2 * bits + 3), None);
+        assert_eq!(shared.count_nones(), 0);
+    }
+
+    #[test]
+    fn counts_nones_past_byte_sums() {
+        // 256 fully set bytes used to wrap a `u8` sum back to zero.
+        let bits = 256 * 8 / OptionMap::STORAGE_BITS as usize;
+        let options = OptionMap::from_slice(&vec![usize::MAX; bits]);
+        assert!(options.contains_nones());
+        assert_eq!(options.null_count(), 256 * 8);
+
+        // A single `None` past the first eighth of the containers.
+        let mut containers = vec![0; 4 * OptionMap::NONES_CHUNK_SIZE];
+        *containers.last_mut().unwrap() = 1 << 3;
+        let options = OptionMap::from_slice(&containers);
+        assert!(options.contains_nones());
+        assert_eq!(options.count_nones(), 1);
+    }
+
+    #[test]
+    fn null_count_tracks_set() {
+        let mut options = OptionMap::new_full(3 * OptionMap::NONES_CHUNK_SIZE + 7);
+        for idx in (0..options.len()).step_by(3) {
+            options.set(idx, true);
+        }
+        options.set(0, true);
+        options.set(3, false);
+        options.set(4, false);
+        assert_eq!(options.null_count(), options.count_nones());
+        assert_eq!(
+            options.null_count(),
+            options.iter().filter(|&none| none).count()
+        );
+        assert!(options.contains_nones());
+
+        for idx in 0..options.len() {
+            options.set(idx, false);
+        }
+        assert_eq!(options.null_count(), 0);
+        assert!(!options.contains_nones());
+    }
+
+    fn map(len: usize, none: impl Fn(usize) -> bool) -> OptionMap {
+        let mut options = OptionMap::new_full(len);
+        (0..len)
+            .filter(|&idx| none(idx))
+            .for_each(|idx| options.set(idx, true));
+        options
+    }
+
+    #[test]
+    fn bitwise_algebra() {
+        let len = 2 * OptionMap::STORAGE_BITS as usize + 5;
+        let a = map(len, |idx| idx % 2 == 0);
+        let b = map(len, |idx| idx % 3 == 0);
+        let expect = |op: fn(bool, bool) -> bool| map(len, |idx| op(idx % 2 == 0, idx % 3 == 0));
+
+        assert_eq!(a.and(&b), expect(|a, b| a && b));
+        assert_eq!(a.or(&b), expect(|a, b| a || b));
+        assert_eq!(a.xor(&b), expect(|a, b| a != b));
+        assert_eq!(a.and_not(&b), expect(|a, b| a && !b));
+        assert_eq!(a.not(), expect(|a, _| !a));
+        assert_eq!(a.not().null_count(), a.not().count_nones());
+        assert_eq!(a.not().not(), a);
+
+        let mut c = a.clone();
+        c.and_in_place(&b);
+        assert_eq!(c, a.and(&b));
+        c.or_in_place(&a);
+        assert_eq!(c, a);
+        c.xor_in_place(&b);
+        assert_eq!(c, a.xor(&b));
+        c.and_not_in_place(&b);
+        assert_eq!(c, a.xor(&b).and_not(&b));
+        c.not_in_place();
+        assert_eq!(c, a.xor(&b).and_not(&b).not());
+        assert_eq!(a, map(len, |idx| idx % 2 == 0));
+    }
+
+    #[test]
+    #[should_panic(expected = "OptionMap lengths differ: 3 and 4")]
+    fn bitwise_lengths_differ() {
+        OptionMap::new_full(3).and(&OptionMap::new_full(4));
+    }
+
+    #[test]
+    fn builds_from_bools() {
+        let bits = OptionMap::STORAGE_BITS as usize;
+        let expect: Vec<bool> = (0..3 * bits + 7).map(|idx| idx % 5 == 0).collect();
+        let options: OptionMap = expect.iter().copied().collect();
+        assert!(options.iter().eq(expect.iter().copied()));
+        assert_eq!(options, map(expect.len(), |idx| idx % 5 == 0));
+        assert_eq!(options.null_count(), options.count_nones());
+    }
+
+    #[test]
+    fn builder_appends_runs() {
+        let bits = OptionMap::STORAGE_BITS as usize;
+        let runs = [
+            (true, 3),
+            (false, bits),
+            (true, 2 * bits + 1),
+            (true, 0),
+            (false, 5),
+        ];
+        let mut builder = OptionMapBuilder::new();
+        let mut expect = Vec::new();
+        for (none, n) in runs {
+            builder.append_n(none, n);
+            builder.append(!none);
+            expect.extend(std::iter::repeat_n(none, n));
+            expect.push(!none);
+        }
+        assert_eq!(builder.len(), expect.len());
+        let options = builder.finish();
+        assert!(options.iter().eq(expect.iter().copied()));
+        assert_eq!(options, expect.into_iter().collect());
+        assert_eq!(options.null_count(), options.count_nones());
+    }
+
+    #[test]
+    fn splits_options() {
+        let (options, values) =
+            OptionMap::from_options((0..200).map(|i| (i % 3 != 0).then_some(i)));
+        assert_eq!(options, map(200, |idx| idx % 3 != 0).not());
+        assert_eq!(values, (0..200).filter(|i| i % 3 != 0).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn iterates_indices_and_runs() {
+        let bits = OptionMap::STORAGE_BITS as usize;
+        for len in [0, 1, bits - 1, bits, 3 * bits + 5] {
+            for none in [
+                |_: usize| false,
+                |_: usize| true,
+                |idx: usize| idx % 7 < 3,
+                |idx: usize| idx / 70 % 2 == 0,
+            ] {
+                let options = map(len, none);
+                let expect = |some: bool| (0..len).filter(move |&idx| none(idx) != some);
+                assert!(options.iter_some_indices().eq(expect(true)));
+                assert!(options.iter_none_indices().eq(expect(false)));
+
+                let mut next = 0;
+                for (start, run, is_some) in options.iter_runs() {
+                    assert_eq!(start, next);
+                    assert!(run > 0);
+                    assert!((start..start + run).all(|idx| none(idx) != is_some));
+                    assert!(start + run == len || none(start + run) == is_some);
+                    next = start + run;
+                }
+                assert_eq!(next, len);
+            }
+        }
+    }
+
+    #[test]
+    fn ranks_and_selects() {
+        let bits = OptionMap::STORAGE_BITS as usize;
+        let len = 20 * OptionMapIndex::SELECT_SAMPLE + 3;
+        for none in [
+            |_: usize| false,
+            |_: usize| true,
+            |idx: usize| idx % 3 == 0,
+            |idx: usize| idx % 997 != 0,
+            |idx: usize| idx % 100 != 0 || idx > 100_000,
+            |idx: usize| idx / 576 % 2 == 0,
+        ] {
+            for len in [0, 1, bits, len, 300 * OptionMapIndex::SELECT_SAMPLE] {
+                let options = map(len, none);
+                let index = OptionMapIndex::new(options.clone());
+                let somes: Vec<usize> = options.iter_some_indices().collect();
+                for idx in 0..=len {
+                    assert_eq!(index.rank(idx), somes.partition_point(|&some| some < idx));
+                }
+                for (rank, &idx) in somes.iter().enumerate() {
+                    assert_eq!(index.select(rank), Some(idx));
+                }
+                assert_eq!(index.select(somes.len()), None);
+            }
+        }
+    }
+
+    #[test]
+    fn samples_sparse_runs() {
+        // Every 100th element for a while spreads 512 `Some`s over more
+        // than `SPARSE_BLOCKS` blocks, then they are all next to each other.
+        let options = map(200_000, |idx| idx % 100 != 0 && idx < 100_000);
+        let index = OptionMapIndex::new(options);
+        assert_eq!(index.positions.len(), 2 * OptionMapIndex::SELECT_SAMPLE);
+        assert_eq!(index.select(1000), Some(100_000));
+        assert_eq!(index.select(1023), Some(100_023));
+        assert_eq!(index.select(1024), Some(100_024));
+        assert_eq!(index.select(100_999), Some(199_999));
+    }
+
+    #[test]
+    fn selects_in_container() {
+        for bits in [
+            1,
+            0b1011_0000_0001,
+            BitContainer::MAX,
+            0x8040_2010_0804_0201,
+            1 << 63,
+        ] {
+            let ones: Vec<u32> = (0..64).filter(|&bit| bits >> bit & 1 == 1).collect();
+            for (rank, &bit) in ones.iter().enumerate() {
+                assert_eq!(select_in_container(bits, rank as u32), bit);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "index 4 out of bounds for length 3")]
+    fn rank_out_of_bounds() {
+        OptionMapIndex::new(OptionMap::new_full(3)).rank(4);
+    }
+
+    #[test]
+    #[should_panic(expected = "index 3 out of bounds for length 3")]
+    fn set_out_of_bounds() {
+        OptionMap::new_full(3).set(3, true);
+    }
+
     #[cfg(feature = "bitvec")]
     mod bitvec {
 